@@ -0,0 +1,309 @@
+use base64::Engine;
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, ZendObject, Zval};
+use ext_php_rs::zend::ClassEntry;
+use serde_json::{Map, Number, Value};
+
+// Tag key marking values JSON cannot represent natively
+const TYPE_KEY: &str = "$type";
+
+// DateTimeInterface::format() pattern keeping microseconds and UTC offset
+const DATETIME_FORMAT: &str = "Y-m-d\\TH:i:s.uP";
+
+/// Encode a row (stdClass) into a JSON object of losslessly encoded column values
+pub fn encode_row(row: &Zval) -> PhpResult<Value> {
+    if row.is_null() {
+        return Ok(Value::Null);
+    }
+
+    let obj = row
+        .object()
+        .ok_or_else(|| PhpException::default("Row data must be an object".into()))?;
+
+    let mut columns = Map::new();
+    for (key, value) in object_properties(obj)?.iter() {
+        columns.insert(key.to_string(), encode_value(value)?);
+    }
+
+    Ok(Value::Object(columns))
+}
+
+/// Decode a JSON object produced by encode_row() back into a stdClass row
+pub fn decode_row(row: &Value) -> PhpResult<Zval> {
+    let mut zval = Zval::new();
+
+    match row {
+        Value::Null => zval.set_null(),
+        // A row without columns comes back from toArray() as an empty PHP array
+        Value::Array(items) if items.is_empty() => {
+            zval.set_object(&mut *new_object("stdClass")?.into_raw());
+        }
+        Value::Object(columns) => {
+            let mut obj = new_object("stdClass")?;
+            for (name, value) in columns {
+                obj.set_property(name, decode_value(value)?)?;
+            }
+            zval.set_object(&mut *obj.into_raw());
+        }
+        _ => {
+            return Err(PhpException::default("Row data must be an array or null".into()).into());
+        }
+    }
+
+    Ok(zval)
+}
+
+/// Encode a single PHP value losslessly
+///
+/// Scalars and lists map to their JSON counterparts. DateTimeInterface instances,
+/// strings that are not valid UTF-8, objects and associative arrays are wrapped
+/// in `{"$type": ..., "value": ...}` so that decode_value() can rebuild them.
+pub fn encode_value(value: &Zval) -> PhpResult<Value> {
+    if value.is_null() {
+        Ok(Value::Null)
+    } else if let Some(b) = value.bool() {
+        Ok(Value::Bool(b))
+    } else if let Some(i) = value.long() {
+        Ok(Value::from(i))
+    } else if let Some(f) = value.double() {
+        Number::from_f64(f).map(Value::Number).ok_or_else(|| {
+            PhpException::default(format!("Cannot encode non-finite float {}", f).into()).into()
+        })
+    } else if let Some(s) = value.zend_str() {
+        Ok(encode_bytes(s.as_bytes()))
+    } else if let Some(arr) = value.array() {
+        if arr.has_sequential_keys() {
+            let items: PhpResult<Vec<Value>> = arr.values().map(encode_value).collect();
+            Ok(Value::Array(items?))
+        } else {
+            let mut entries = Map::new();
+            for (key, item) in arr.iter() {
+                entries.insert(key.to_string(), encode_value(item)?);
+            }
+            Ok(tagged("array", Value::Object(entries)))
+        }
+    } else if let Some(obj) = value.object() {
+        if is_datetime(obj) {
+            let formatted = value.try_call_method("format", vec![&DATETIME_FORMAT])?;
+            let timezone = value
+                .try_call_method("getTimezone", vec![])?
+                .try_call_method("getName", vec![])?;
+
+            let mut datetime = Map::new();
+            datetime.insert(TYPE_KEY.to_string(), Value::from("datetime"));
+            datetime.insert(
+                "value".to_string(),
+                Value::from(formatted.string().unwrap_or_default()),
+            );
+            datetime.insert(
+                "timezone".to_string(),
+                Value::from(timezone.string().unwrap_or_default()),
+            );
+            Ok(Value::Object(datetime))
+        } else if obj.get_class_name()? == "stdClass" {
+            let mut properties = Map::new();
+            for (key, item) in object_properties(obj)?.iter() {
+                properties.insert(key.to_string(), encode_value(item)?);
+            }
+            Ok(tagged("object", Value::Object(properties)))
+        } else {
+            Err(PhpException::default(
+                format!("Cannot encode object of class {}", obj.get_class_name()?).into(),
+            )
+            .into())
+        }
+    } else {
+        Err(PhpException::default("Cannot encode value of unsupported type".into()).into())
+    }
+}
+
+/// Encode a byte string, tagged as binary in base64 when it is not valid UTF-8
+pub fn encode_bytes(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Value::String(text.to_string()),
+        Err(_) => tagged(
+            "binary",
+            Value::String(base64::engine::general_purpose::STANDARD.encode(bytes)),
+        ),
+    }
+}
+
+/// Decode a value produced by encode_value() back into its PHP form
+pub fn decode_value(value: &Value) -> PhpResult<Zval> {
+    let mut zval = Zval::new();
+
+    match value {
+        Value::Null => zval.set_null(),
+        Value::Bool(b) => zval.set_bool(*b),
+        Value::Number(n) => set_number(&mut zval, n)?,
+        Value::String(s) => zval.set_string(s, false)?,
+        Value::Array(items) => {
+            let mut arr = ZendHashTable::new();
+            for item in items {
+                arr.push(decode_value(item)?)?;
+            }
+            zval.set_hashtable(arr);
+        }
+        Value::Object(entries) => {
+            let tag = entries.get(TYPE_KEY).and_then(Value::as_str);
+            let inner = entries.get("value").unwrap_or(&Value::Null);
+
+            match (tag, inner) {
+                (Some("datetime"), Value::String(datetime)) => {
+                    let obj = new_object("DateTimeImmutable")?;
+                    obj.try_call_method("__construct", vec![&datetime.as_str()])?;
+                    zval.set_object(&mut *obj.into_raw());
+
+                    if let Some(timezone) = entries.get("timezone").and_then(Value::as_str) {
+                        let tz = new_object("DateTimeZone")?;
+                        tz.try_call_method("__construct", vec![&timezone])?;
+                        let mut tz_zval = Zval::new();
+                        tz_zval.set_object(&mut *tz.into_raw());
+                        zval = zval.try_call_method("setTimezone", vec![&tz_zval])?;
+                    }
+                }
+                (Some("binary"), Value::String(encoded)) => {
+                    let bytes = base64::engine::general_purpose::STANDARD
+                        .decode(encoded)
+                        .map_err(|e| {
+                            PhpException::default(format!("Invalid binary value: {}", e).into())
+                        })?;
+                    zval.set_binary(bytes);
+                }
+                (Some("array"), Value::Object(items)) => {
+                    let mut arr = ZendHashTable::new();
+                    for (key, item) in items {
+                        arr.insert(key.as_str(), decode_value(item)?)?;
+                    }
+                    zval.set_hashtable(arr);
+                }
+                (Some("object"), Value::Object(properties)) => {
+                    let mut obj = new_object("stdClass")?;
+                    for (name, item) in properties {
+                        obj.set_property(name, decode_value(item)?)?;
+                    }
+                    zval.set_object(&mut *obj.into_raw());
+                }
+                // An object without properties comes back from toArray() as an empty PHP array
+                (Some("object"), Value::Array(items)) if items.is_empty() => {
+                    zval.set_object(&mut *new_object("stdClass")?.into_raw());
+                }
+                _ => {
+                    return Err(PhpException::default(
+                        format!("Invalid encoded value: {}", value).into(),
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+
+    Ok(zval)
+}
+
+/// Strip the tags added by encode_value(), for consumers that expect plain JSON
+///
+/// Datetimes become their formatted string, binary values their base64 form and
+/// objects/associative arrays plain JSON objects.
+pub fn plain_value(value: &Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.iter().map(plain_value).collect()),
+        Value::Object(entries) => match entries.get("value") {
+            Some(Value::Object(inner)) if entries.contains_key(TYPE_KEY) => Value::Object(
+                inner
+                    .iter()
+                    .map(|(key, item)| (key.clone(), plain_value(item)))
+                    .collect(),
+            ),
+            Some(inner) if entries.contains_key(TYPE_KEY) => inner.clone(),
+            _ => Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, item)| (key.clone(), plain_value(item)))
+                    .collect(),
+            ),
+        },
+        _ => value.clone(),
+    }
+}
+
+/// Convert a plain PHP value (scalars and arrays) into JSON
+pub fn zval_to_json(value: &Zval) -> PhpResult<Value> {
+    if let Some(arr) = value.array() {
+        if arr.has_sequential_keys() {
+            let items: PhpResult<Vec<Value>> = arr.values().map(zval_to_json).collect();
+            Ok(Value::Array(items?))
+        } else {
+            let mut entries = Map::new();
+            for (key, item) in arr.iter() {
+                entries.insert(key.to_string(), zval_to_json(item)?);
+            }
+            Ok(Value::Object(entries))
+        }
+    } else {
+        encode_value(value)
+    }
+}
+
+/// Convert JSON into a plain PHP value, JSON objects become associative arrays
+pub fn json_to_zval(value: &Value) -> PhpResult<Zval> {
+    let mut zval = Zval::new();
+
+    match value {
+        Value::Null => zval.set_null(),
+        Value::Bool(b) => zval.set_bool(*b),
+        Value::Number(n) => set_number(&mut zval, n)?,
+        Value::String(s) => zval.set_string(s, false)?,
+        Value::Array(items) => {
+            let mut arr = ZendHashTable::new();
+            for item in items {
+                arr.push(json_to_zval(item)?)?;
+            }
+            zval.set_hashtable(arr);
+        }
+        Value::Object(entries) => {
+            let mut arr = ZendHashTable::new();
+            for (key, item) in entries {
+                arr.insert(key.as_str(), json_to_zval(item)?)?;
+            }
+            zval.set_hashtable(arr);
+        }
+    }
+
+    Ok(zval)
+}
+
+fn set_number(zval: &mut Zval, n: &Number) -> PhpResult<()> {
+    if let Some(i) = n.as_i64() {
+        zval.set_long(i);
+    } else if let Some(f) = n.as_f64() {
+        zval.set_double(f);
+    } else {
+        zval.set_string(&n.to_string(), false)?;
+    }
+    Ok(())
+}
+
+fn tagged(tag: &str, value: Value) -> Value {
+    let mut entries = Map::new();
+    entries.insert(TYPE_KEY.to_string(), Value::from(tag));
+    entries.insert("value".to_string(), value);
+    Value::Object(entries)
+}
+
+fn is_datetime(obj: &ZendObject) -> bool {
+    ClassEntry::try_find("DateTimeInterface")
+        .map(|ce| obj.instance_of(ce))
+        .unwrap_or(false)
+}
+
+fn object_properties(obj: &ZendObject) -> PhpResult<&ZendHashTable> {
+    obj.get_properties()
+        .map_err(|e| PhpException::default(format!("Failed to read properties: {}", e).into()))
+}
+
+fn new_object(class_name: &str) -> PhpResult<ext_php_rs::boxed::ZBox<ZendObject>> {
+    let ce = ClassEntry::try_find(class_name)
+        .ok_or_else(|| PhpException::default(format!("{} class not found", class_name).into()))?;
+    Ok(ZendObject::new(ce))
+}
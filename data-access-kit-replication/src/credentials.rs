@@ -0,0 +1,59 @@
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendCallable, Zval};
+
+/// Connection credentials, intentionally without Debug so they cannot end up in logs
+pub struct Credentials {
+    pub user: Option<String>,
+    pub password: String,
+}
+
+/// Rust wrapper for a PHP callable returning connection credentials
+///
+/// The callable is called on every connect, so rotated secrets are picked up
+/// when the stream reconnects.
+pub struct CredentialsProvider {
+    php_callable: Zval,
+}
+
+impl CredentialsProvider {
+    /// Create a new credentials provider wrapper from a PHP callable
+    pub fn new(php_callable: &Zval) -> PhpResult<Self> {
+        if !php_callable.is_callable() {
+            return Err(PhpException::default(
+                "Credentials provider must be a callable".into(),
+            ));
+        }
+
+        // Use shallow_clone to safely store the Zval reference
+        Ok(CredentialsProvider {
+            php_callable: php_callable.shallow_clone(),
+        })
+    }
+
+    /// Call the provider, it returns ['user' => ?string, 'password' => string]
+    pub fn credentials(&self) -> PhpResult<Credentials> {
+        let result = ZendCallable::new(&self.php_callable)?.try_call(vec![])?;
+
+        let invalid = || {
+            PhpException::default(
+                "Credentials provider must return an array with a password and an optional user"
+                    .into(),
+            )
+        };
+
+        let arr = result.array().ok_or_else(invalid)?;
+
+        let password = arr
+            .get("password")
+            .and_then(|password| password.string())
+            .ok_or_else(invalid)?;
+
+        let user = match arr.get("user") {
+            None => None,
+            Some(user) if user.is_null() => None,
+            Some(user) => Some(user.string().ok_or_else(invalid)?),
+        };
+
+        Ok(Credentials { user, password })
+    }
+}
@@ -0,0 +1,49 @@
+use ext_php_rs::prelude::*;
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod cloudevents;
+mod debezium;
+
+pub use cloudevents::CloudEventsEncoder;
+pub use debezium::DebeziumEncoder;
+
+/// Milliseconds since the Unix epoch, used as the processing time of an encoded event
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn to_json_string(value: &Value) -> PhpResult<String> {
+    serde_json::to_string(value)
+        .map_err(|e| PhpException::default(format!("Failed to encode JSON: {}", e).into()))
+}
+
+/// Format a Unix timestamp as an RFC 3339 UTC date-time
+fn rfc3339(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+
+    // Civil date from days since epoch (Howard Hinnant's days_from_civil inverse)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
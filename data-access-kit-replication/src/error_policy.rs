@@ -0,0 +1,110 @@
+use crate::{codec, Logger};
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendCallable, ZendHashTable, Zval};
+use serde_json::Value;
+
+/// What to do with a row the stream cannot deliver (filter or conversion failure)
+#[derive(Debug, Default)]
+pub enum ErrorPolicy {
+    /// Log the failure and continue with the next row
+    #[default]
+    Skip,
+    /// Stop with an exception, the checkpoint of the failed event is not saved
+    Throw,
+    /// Hand the raw row to a PHP callable and continue
+    DeadLetter(Zval),
+}
+
+/// A row that could not be delivered, with everything needed to handle it later
+pub struct DeadLetter<'a> {
+    pub reason: &'a str,
+    pub error: String,
+    pub r#type: &'a str,
+    pub schema: &'a str,
+    pub table: &'a str,
+    pub checkpoint: &'a str,
+    pub ordinal: usize,
+    pub before: Option<Vec<(String, Value)>>,
+    pub after: Option<Vec<(String, Value)>>,
+}
+
+impl ErrorPolicy {
+    /// Create the policy from 'skip', 'throw' or a dead-letter callable
+    pub fn new(policy: &Zval) -> PhpResult<Self> {
+        match policy.str() {
+            Some("skip") => Ok(ErrorPolicy::Skip),
+            Some("throw") => Ok(ErrorPolicy::Throw),
+            _ if policy.is_callable() => Ok(ErrorPolicy::DeadLetter(policy.shallow_clone())),
+            _ => Err(PhpException::default(
+                "Stream option 'on_error' must be 'skip', 'throw' or a callable".into(),
+            )),
+        }
+    }
+
+    /// Handle an undeliverable row, returns an error when the stream should stop
+    pub fn handle(&self, logger: &Logger, letter: DeadLetter) -> PhpResult<()> {
+        let message = match letter.reason {
+            "filter" => "Filter failed",
+            _ => "Failed to create event",
+        };
+
+        match self {
+            ErrorPolicy::Skip => {
+                logger.error(
+                    &format!("{}, skipping row", message),
+                    &[
+                        ("type", letter.r#type),
+                        ("schema", letter.schema),
+                        ("table", letter.table),
+                        ("checkpoint", letter.checkpoint),
+                        ("error", &letter.error),
+                    ],
+                );
+                Ok(())
+            }
+            ErrorPolicy::Throw => Err(PhpException::default(
+                format!(
+                    "{} for {} event on {}.{} at checkpoint {}: {}",
+                    message,
+                    letter.r#type,
+                    letter.schema,
+                    letter.table,
+                    letter.checkpoint,
+                    letter.error
+                )
+                .into(),
+            )),
+            ErrorPolicy::DeadLetter(callable) => {
+                let mut data = ZendHashTable::new();
+                data.insert("reason", letter.reason)?;
+                data.insert("error", letter.error.as_str())?;
+                data.insert("type", letter.r#type)?;
+                data.insert("schema", letter.schema)?;
+                data.insert("table", letter.table)?;
+                data.insert("checkpoint", letter.checkpoint)?;
+                data.insert("ordinal", letter.ordinal as i64)?;
+                data.insert("before", raw_row(letter.before)?)?;
+                data.insert("after", raw_row(letter.after)?)?;
+
+                ZendCallable::new(callable)?.try_call(vec![&data])?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Column name to raw binlog value, null when the event has no such row image
+fn raw_row(row: Option<Vec<(String, Value)>>) -> PhpResult<Zval> {
+    let mut zval = Zval::new();
+    match row {
+        Some(columns) => {
+            let mut arr = ZendHashTable::new();
+            for (name, value) in columns {
+                arr.insert(name.as_str(), codec::json_to_zval(&value)?)?;
+            }
+            zval.set_hashtable(arr);
+        }
+        None => zval.set_null(),
+    }
+    Ok(zval)
+}
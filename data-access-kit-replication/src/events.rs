@@ -1,3 +1,4 @@
+use crate::codec;
 use ext_php_rs::convert::{FromZval, IntoZval};
 use ext_php_rs::error::Result;
 use ext_php_rs::ffi;
@@ -5,6 +6,7 @@ use ext_php_rs::flags::{ClassFlags, DataType};
 use ext_php_rs::prelude::*;
 use ext_php_rs::types::Zval;
 use ext_php_rs::zend::ClassEntry;
+use serde_json::{Map, Value};
 use std::ffi::CString;
 use std::{mem, ptr};
 
@@ -20,6 +22,11 @@ pub fn event_interface_ce() -> &'static ClassEntry {
     }
 }
 
+// JsonSerializable is provided by the json extension that is always compiled into PHP
+pub fn json_serializable_ce() -> &'static ClassEntry {
+    ClassEntry::try_find("JsonSerializable").expect("JsonSerializable not available")
+}
+
 // Unsafe function to register EventInterface
 pub unsafe fn register_event_interface() {
     // Create and register EventInterface
@@ -91,6 +98,10 @@ impl Mixed {
     pub fn new(val: &Zval) -> Self {
         Mixed(val.shallow_clone())
     }
+
+    pub fn as_zval(&self) -> &Zval {
+        &self.0
+    }
 }
 
 impl IntoZval for Mixed {
@@ -111,9 +122,199 @@ impl<'a> FromZval<'a> for Mixed {
     }
 }
 
+/// Version of the array shape, 2 added `query` and `columnTypes`
+const ARRAY_VERSION: i64 = 2;
+
+/// Fields common to all event classes, used for array/JSON (de)serialization
+///
+/// The array shape is versioned: all keys of a version are always present, `before`/`after`
+/// are null when the event type does not carry them. Column values are encoded by
+/// codec::encode_value(), so decimals stay strings and DateTimeImmutable and binary
+/// values survive the round trip.
+pub struct EventRecord {
+    pub r#type: String,
+    pub timestamp: i64,
+    pub checkpoint: String,
+    pub schema: String,
+    pub table: String,
+    pub server_id: i64,
+    pub binlog_file: String,
+    pub binlog_position: i64,
+    pub gtid: Option<String>,
+    pub ordinal: i64,
+    pub query: Option<String>,
+    /// Column name to binlog type, null when unknown
+    pub column_types: Value,
+    pub before: Option<Mixed>,
+    pub after: Option<Mixed>,
+}
+
+impl EventRecord {
+    pub fn to_json(&self) -> PhpResult<Value> {
+        let mut data = Map::new();
+        data.insert("version".to_string(), Value::from(ARRAY_VERSION));
+        data.insert("type".to_string(), Value::from(self.r#type.as_str()));
+        data.insert("timestamp".to_string(), Value::from(self.timestamp));
+        data.insert(
+            "checkpoint".to_string(),
+            Value::from(self.checkpoint.as_str()),
+        );
+        data.insert("schema".to_string(), Value::from(self.schema.as_str()));
+        data.insert("table".to_string(), Value::from(self.table.as_str()));
+        data.insert("serverId".to_string(), Value::from(self.server_id));
+        data.insert(
+            "binlogFile".to_string(),
+            Value::from(self.binlog_file.as_str()),
+        );
+        data.insert(
+            "binlogPosition".to_string(),
+            Value::from(self.binlog_position),
+        );
+        data.insert("gtid".to_string(), Value::from(self.gtid.clone()));
+        data.insert("ordinal".to_string(), Value::from(self.ordinal));
+        data.insert("query".to_string(), Value::from(self.query.clone()));
+        data.insert("columnTypes".to_string(), self.column_types.clone());
+        data.insert("before".to_string(), Self::encode_row(&self.before)?);
+        data.insert("after".to_string(), Self::encode_row(&self.after)?);
+        Ok(Value::Object(data))
+    }
+
+    pub fn from_json(data: &Value, expected_type: &str) -> PhpResult<Self> {
+        let data = data
+            .as_object()
+            .ok_or_else(|| PhpException::default("Event data must be an array".into()))?;
+
+        let string = |key: &str| -> PhpResult<String> {
+            data.get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    PhpException::default(format!("Event data is missing string '{}'", key).into())
+                })
+        };
+        let int = |key: &str| -> PhpResult<i64> {
+            data.get(key).and_then(Value::as_i64).ok_or_else(|| {
+                PhpException::default(format!("Event data is missing integer '{}'", key).into())
+            })
+        };
+
+        // Arrays without a version were written before it was added, i.e. version 1
+        let version = match data.get("version") {
+            Some(version) => version.as_i64().ok_or_else(|| {
+                PhpException::default("Event data 'version' must be an integer".into())
+            })?,
+            None => 1,
+        };
+        if !(1..=ARRAY_VERSION).contains(&version) {
+            return Err(PhpException::default(
+                format!("Unsupported event data version {}", version).into(),
+            ));
+        }
+
+        let r#type = string("type")?;
+        if r#type != expected_type {
+            return Err(PhpException::default(
+                format!("Expected event type {}, got {}", expected_type, r#type).into(),
+            ));
+        }
+
+        Ok(EventRecord {
+            r#type,
+            timestamp: int("timestamp")?,
+            checkpoint: string("checkpoint")?,
+            schema: string("schema")?,
+            table: string("table")?,
+            server_id: int("serverId")?,
+            binlog_file: string("binlogFile")?,
+            binlog_position: int("binlogPosition")?,
+            gtid: data.get("gtid").and_then(Value::as_str).map(str::to_string),
+            ordinal: int("ordinal")?,
+            query: data
+                .get("query")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            column_types: data.get("columnTypes").cloned().unwrap_or(Value::Null),
+            before: Self::decode_row(data.get("before"))?,
+            after: Self::decode_row(data.get("after"))?,
+        })
+    }
+
+    /// Read the record from any object implementing EventInterface
+    pub fn from_object(event: &Zval) -> PhpResult<Self> {
+        let obj = event.object().ok_or_else(|| {
+            PhpException::default("Event must be an object implementing EventInterface".into())
+        })?;
+
+        let r#type: String = obj.get_property("type")?;
+        let has_before = r#type == "UPDATE" || r#type == "DELETE";
+        let has_after = r#type == "INSERT" || r#type == "UPDATE";
+
+        Ok(EventRecord {
+            timestamp: obj.get_property("timestamp")?,
+            checkpoint: obj.get_property("checkpoint")?,
+            schema: obj.get_property("schema")?,
+            table: obj.get_property("table")?,
+            server_id: obj.get_property::<Option<i64>>("serverId")?.unwrap_or(0),
+            binlog_file: obj
+                .get_property::<Option<String>>("binlogFile")?
+                .unwrap_or_default(),
+            binlog_position: obj
+                .get_property::<Option<i64>>("binlogPosition")?
+                .unwrap_or(0),
+            gtid: obj.get_property("gtid")?,
+            ordinal: obj.get_property::<Option<i64>>("ordinal")?.unwrap_or(0),
+            query: obj.get_property("query")?,
+            column_types: match obj.get_property::<Option<Mixed>>("columnTypes")? {
+                Some(Mixed(zval)) if !zval.is_null() => codec::zval_to_json(&zval)?,
+                _ => Value::Null,
+            },
+            before: if has_before {
+                Some(obj.get_property("before")?)
+            } else {
+                None
+            },
+            after: if has_after {
+                Some(obj.get_property("after")?)
+            } else {
+                None
+            },
+            r#type,
+        })
+    }
+
+    pub fn to_array(&self) -> PhpResult<Zval> {
+        codec::json_to_zval(&self.to_json()?)
+    }
+
+    pub fn from_array(data: &Zval, expected_type: &str) -> PhpResult<Self> {
+        Self::from_json(&codec::zval_to_json(data)?, expected_type)
+    }
+
+    fn encode_row(row: &Option<Mixed>) -> PhpResult<Value> {
+        match row {
+            Some(Mixed(zval)) => codec::encode_row(zval),
+            None => Ok(Value::Null),
+        }
+    }
+
+    fn decode_row(row: Option<&Value>) -> PhpResult<Option<Mixed>> {
+        match row {
+            Some(Value::Null) | None => Ok(None),
+            Some(value) => Ok(Some(Mixed(codec::decode_row(value)?))),
+        }
+    }
+
+    fn take_row(row: Option<Mixed>, key: &str) -> PhpResult<Mixed> {
+        row.ok_or_else(|| {
+            PhpException::default(format!("Event data is missing row '{}'", key).into())
+        })
+    }
+}
+
 #[php_class]
 #[php(name = "DataAccessKit\\Replication\\InsertEvent")]
 #[php(implements(ce = event_interface_ce, stub = "DataAccessKit\\Replication\\EventInterface"))]
+#[php(implements(ce = json_serializable_ce, stub = "JsonSerializable"))]
 pub struct InsertEvent {
     #[php(prop, name = "type")]
     r#type: String,
@@ -127,10 +328,18 @@ pub struct InsertEvent {
     table: String,
     #[php(prop)]
     after: Mixed,
+    server_id: i64,
+    binlog_file: String,
+    binlog_position: i64,
+    gtid: Option<String>,
+    ordinal: i64,
+    query: Option<String>,
+    column_types: Mixed,
 }
 
 #[php_impl]
 impl InsertEvent {
+    #[php(optional = server_id)]
     pub fn __construct(
         r#type: String,
         timestamp: i64,
@@ -138,6 +347,13 @@ impl InsertEvent {
         schema: String,
         table: String,
         after: &Zval,
+        server_id: Option<i64>,
+        binlog_file: Option<String>,
+        binlog_position: Option<i64>,
+        gtid: Option<String>,
+        ordinal: Option<i64>,
+        query: Option<String>,
+        column_types: Option<&Zval>,
     ) -> PhpResult<Self> {
         Ok(InsertEvent {
             r#type,
@@ -146,6 +362,96 @@ impl InsertEvent {
             schema,
             table,
             after: Mixed::new(after),
+            server_id: server_id.unwrap_or(0),
+            binlog_file: binlog_file.unwrap_or_default(),
+            binlog_position: binlog_position.unwrap_or(0),
+            gtid,
+            ordinal: ordinal.unwrap_or(0),
+            query,
+            column_types: column_types.map_or_else(|| Mixed(Zval::new()), Mixed::new),
+        })
+    }
+
+    #[php(getter, name = "serverId")]
+    pub fn get_server_id(&self) -> i64 {
+        self.server_id
+    }
+
+    #[php(getter, name = "binlogFile")]
+    pub fn get_binlog_file(&self) -> String {
+        self.binlog_file.clone()
+    }
+
+    #[php(getter, name = "binlogPosition")]
+    pub fn get_binlog_position(&self) -> i64 {
+        self.binlog_position
+    }
+
+    #[php(getter)]
+    pub fn get_gtid(&self) -> Option<String> {
+        self.gtid.clone()
+    }
+
+    #[php(getter)]
+    pub fn get_ordinal(&self) -> i64 {
+        self.ordinal
+    }
+
+    #[php(getter)]
+    pub fn get_query(&self) -> Option<String> {
+        self.query.clone()
+    }
+
+    #[php(getter, name = "columnTypes")]
+    pub fn get_column_types(&self) -> Mixed {
+        self.column_types.clone()
+    }
+
+    pub fn to_array(&self) -> PhpResult<Zval> {
+        self.record()?.to_array()
+    }
+
+    pub fn json_serialize(&self) -> PhpResult<Zval> {
+        self.to_array()
+    }
+
+    pub fn from_array(data: &Zval) -> PhpResult<Self> {
+        let record = EventRecord::from_array(data, "INSERT")?;
+        Ok(InsertEvent {
+            r#type: record.r#type,
+            timestamp: record.timestamp,
+            checkpoint: record.checkpoint,
+            schema: record.schema,
+            table: record.table,
+            after: EventRecord::take_row(record.after, "after")?,
+            server_id: record.server_id,
+            binlog_file: record.binlog_file,
+            binlog_position: record.binlog_position,
+            gtid: record.gtid,
+            ordinal: record.ordinal,
+            query: record.query,
+            column_types: Mixed(codec::json_to_zval(&record.column_types)?),
+        })
+    }
+}
+
+impl InsertEvent {
+    fn record(&self) -> PhpResult<EventRecord> {
+        Ok(EventRecord {
+            r#type: self.r#type.clone(),
+            timestamp: self.timestamp,
+            checkpoint: self.checkpoint.clone(),
+            schema: self.schema.clone(),
+            table: self.table.clone(),
+            server_id: self.server_id,
+            binlog_file: self.binlog_file.clone(),
+            binlog_position: self.binlog_position,
+            gtid: self.gtid.clone(),
+            ordinal: self.ordinal,
+            query: self.query.clone(),
+            column_types: codec::zval_to_json(&self.column_types.0)?,
+            before: None,
+            after: Some(self.after.clone()),
         })
     }
 }
@@ -153,6 +459,7 @@ impl InsertEvent {
 #[php_class]
 #[php(name = "DataAccessKit\\Replication\\UpdateEvent")]
 #[php(implements(ce = event_interface_ce, stub = "DataAccessKit\\Replication\\EventInterface"))]
+#[php(implements(ce = json_serializable_ce, stub = "JsonSerializable"))]
 pub struct UpdateEvent {
     #[php(prop, name = "type")]
     r#type: String,
@@ -168,10 +475,18 @@ pub struct UpdateEvent {
     before: Mixed,
     #[php(prop)]
     after: Mixed,
+    server_id: i64,
+    binlog_file: String,
+    binlog_position: i64,
+    gtid: Option<String>,
+    ordinal: i64,
+    query: Option<String>,
+    column_types: Mixed,
 }
 
 #[php_impl]
 impl UpdateEvent {
+    #[php(optional = server_id)]
     pub fn __construct(
         r#type: String,
         timestamp: i64,
@@ -180,6 +495,13 @@ impl UpdateEvent {
         table: String,
         before: &Zval,
         after: &Zval,
+        server_id: Option<i64>,
+        binlog_file: Option<String>,
+        binlog_position: Option<i64>,
+        gtid: Option<String>,
+        ordinal: Option<i64>,
+        query: Option<String>,
+        column_types: Option<&Zval>,
     ) -> PhpResult<Self> {
         Ok(UpdateEvent {
             r#type,
@@ -189,6 +511,97 @@ impl UpdateEvent {
             table,
             before: Mixed::new(before),
             after: Mixed::new(after),
+            server_id: server_id.unwrap_or(0),
+            binlog_file: binlog_file.unwrap_or_default(),
+            binlog_position: binlog_position.unwrap_or(0),
+            gtid,
+            ordinal: ordinal.unwrap_or(0),
+            query,
+            column_types: column_types.map_or_else(|| Mixed(Zval::new()), Mixed::new),
+        })
+    }
+
+    #[php(getter, name = "serverId")]
+    pub fn get_server_id(&self) -> i64 {
+        self.server_id
+    }
+
+    #[php(getter, name = "binlogFile")]
+    pub fn get_binlog_file(&self) -> String {
+        self.binlog_file.clone()
+    }
+
+    #[php(getter, name = "binlogPosition")]
+    pub fn get_binlog_position(&self) -> i64 {
+        self.binlog_position
+    }
+
+    #[php(getter)]
+    pub fn get_gtid(&self) -> Option<String> {
+        self.gtid.clone()
+    }
+
+    #[php(getter)]
+    pub fn get_ordinal(&self) -> i64 {
+        self.ordinal
+    }
+
+    #[php(getter)]
+    pub fn get_query(&self) -> Option<String> {
+        self.query.clone()
+    }
+
+    #[php(getter, name = "columnTypes")]
+    pub fn get_column_types(&self) -> Mixed {
+        self.column_types.clone()
+    }
+
+    pub fn to_array(&self) -> PhpResult<Zval> {
+        self.record()?.to_array()
+    }
+
+    pub fn json_serialize(&self) -> PhpResult<Zval> {
+        self.to_array()
+    }
+
+    pub fn from_array(data: &Zval) -> PhpResult<Self> {
+        let record = EventRecord::from_array(data, "UPDATE")?;
+        Ok(UpdateEvent {
+            r#type: record.r#type,
+            timestamp: record.timestamp,
+            checkpoint: record.checkpoint,
+            schema: record.schema,
+            table: record.table,
+            before: EventRecord::take_row(record.before, "before")?,
+            after: EventRecord::take_row(record.after, "after")?,
+            server_id: record.server_id,
+            binlog_file: record.binlog_file,
+            binlog_position: record.binlog_position,
+            gtid: record.gtid,
+            ordinal: record.ordinal,
+            query: record.query,
+            column_types: Mixed(codec::json_to_zval(&record.column_types)?),
+        })
+    }
+}
+
+impl UpdateEvent {
+    fn record(&self) -> PhpResult<EventRecord> {
+        Ok(EventRecord {
+            r#type: self.r#type.clone(),
+            timestamp: self.timestamp,
+            checkpoint: self.checkpoint.clone(),
+            schema: self.schema.clone(),
+            table: self.table.clone(),
+            server_id: self.server_id,
+            binlog_file: self.binlog_file.clone(),
+            binlog_position: self.binlog_position,
+            gtid: self.gtid.clone(),
+            ordinal: self.ordinal,
+            query: self.query.clone(),
+            column_types: codec::zval_to_json(&self.column_types.0)?,
+            before: Some(self.before.clone()),
+            after: Some(self.after.clone()),
         })
     }
 }
@@ -196,6 +609,7 @@ impl UpdateEvent {
 #[php_class]
 #[php(name = "DataAccessKit\\Replication\\DeleteEvent")]
 #[php(implements(ce = event_interface_ce, stub = "DataAccessKit\\Replication\\EventInterface"))]
+#[php(implements(ce = json_serializable_ce, stub = "JsonSerializable"))]
 pub struct DeleteEvent {
     #[php(prop, name = "type")]
     r#type: String,
@@ -209,10 +623,18 @@ pub struct DeleteEvent {
     table: String,
     #[php(prop)]
     before: Mixed,
+    server_id: i64,
+    binlog_file: String,
+    binlog_position: i64,
+    gtid: Option<String>,
+    ordinal: i64,
+    query: Option<String>,
+    column_types: Mixed,
 }
 
 #[php_impl]
 impl DeleteEvent {
+    #[php(optional = server_id)]
     pub fn __construct(
         r#type: String,
         timestamp: i64,
@@ -220,6 +642,13 @@ impl DeleteEvent {
         schema: String,
         table: String,
         before: &Zval,
+        server_id: Option<i64>,
+        binlog_file: Option<String>,
+        binlog_position: Option<i64>,
+        gtid: Option<String>,
+        ordinal: Option<i64>,
+        query: Option<String>,
+        column_types: Option<&Zval>,
     ) -> PhpResult<Self> {
         Ok(DeleteEvent {
             r#type,
@@ -228,6 +657,139 @@ impl DeleteEvent {
             schema,
             table,
             before: Mixed::new(before),
+            server_id: server_id.unwrap_or(0),
+            binlog_file: binlog_file.unwrap_or_default(),
+            binlog_position: binlog_position.unwrap_or(0),
+            gtid,
+            ordinal: ordinal.unwrap_or(0),
+            query,
+            column_types: column_types.map_or_else(|| Mixed(Zval::new()), Mixed::new),
         })
     }
+
+    #[php(getter, name = "serverId")]
+    pub fn get_server_id(&self) -> i64 {
+        self.server_id
+    }
+
+    #[php(getter, name = "binlogFile")]
+    pub fn get_binlog_file(&self) -> String {
+        self.binlog_file.clone()
+    }
+
+    #[php(getter, name = "binlogPosition")]
+    pub fn get_binlog_position(&self) -> i64 {
+        self.binlog_position
+    }
+
+    #[php(getter)]
+    pub fn get_gtid(&self) -> Option<String> {
+        self.gtid.clone()
+    }
+
+    #[php(getter)]
+    pub fn get_ordinal(&self) -> i64 {
+        self.ordinal
+    }
+
+    #[php(getter)]
+    pub fn get_query(&self) -> Option<String> {
+        self.query.clone()
+    }
+
+    #[php(getter, name = "columnTypes")]
+    pub fn get_column_types(&self) -> Mixed {
+        self.column_types.clone()
+    }
+
+    pub fn to_array(&self) -> PhpResult<Zval> {
+        self.record()?.to_array()
+    }
+
+    pub fn json_serialize(&self) -> PhpResult<Zval> {
+        self.to_array()
+    }
+
+    pub fn from_array(data: &Zval) -> PhpResult<Self> {
+        let record = EventRecord::from_array(data, "DELETE")?;
+        Ok(DeleteEvent {
+            r#type: record.r#type,
+            timestamp: record.timestamp,
+            checkpoint: record.checkpoint,
+            schema: record.schema,
+            table: record.table,
+            before: EventRecord::take_row(record.before, "before")?,
+            server_id: record.server_id,
+            binlog_file: record.binlog_file,
+            binlog_position: record.binlog_position,
+            gtid: record.gtid,
+            ordinal: record.ordinal,
+            query: record.query,
+            column_types: Mixed(codec::json_to_zval(&record.column_types)?),
+        })
+    }
+}
+
+impl DeleteEvent {
+    fn record(&self) -> PhpResult<EventRecord> {
+        Ok(EventRecord {
+            r#type: self.r#type.clone(),
+            timestamp: self.timestamp,
+            checkpoint: self.checkpoint.clone(),
+            schema: self.schema.clone(),
+            table: self.table.clone(),
+            server_id: self.server_id,
+            binlog_file: self.binlog_file.clone(),
+            binlog_position: self.binlog_position,
+            gtid: self.gtid.clone(),
+            ordinal: self.ordinal,
+            query: self.query.clone(),
+            column_types: codec::zval_to_json(&self.column_types.0)?,
+            before: Some(self.before.clone()),
+            after: None,
+        })
+    }
+}
+
+/// Liveness signal sent by the server while there are no events to stream
+#[php_class]
+#[php(name = "DataAccessKit\\Replication\\HeartbeatEvent")]
+pub struct HeartbeatEvent {
+    #[php(prop)]
+    timestamp: i64,
+    server_id: i64,
+    binlog_file: String,
+    binlog_position: i64,
+}
+
+#[php_impl]
+impl HeartbeatEvent {
+    pub fn __construct(
+        timestamp: i64,
+        server_id: i64,
+        binlog_file: String,
+        binlog_position: i64,
+    ) -> Self {
+        HeartbeatEvent {
+            timestamp,
+            server_id,
+            binlog_file,
+            binlog_position,
+        }
+    }
+
+    #[php(getter, name = "serverId")]
+    pub fn get_server_id(&self) -> i64 {
+        self.server_id
+    }
+
+    #[php(getter, name = "binlogFile")]
+    pub fn get_binlog_file(&self) -> String {
+        self.binlog_file.clone()
+    }
+
+    #[php(getter, name = "binlogPosition")]
+    pub fn get_binlog_position(&self) -> i64 {
+        self.binlog_position
+    }
 }
@@ -0,0 +1,9 @@
+use ext_php_rs::prelude::*;
+use ext_php_rs::zend::ce;
+
+/// Thrown when the server no longer has the binlog a checkpoint points into
+#[php_class]
+#[php(name = "DataAccessKit\\Replication\\CheckpointUnavailableException")]
+#[php(extends(ce = ce::exception, stub = "\\Exception"))]
+#[derive(Default)]
+pub struct CheckpointUnavailableException;
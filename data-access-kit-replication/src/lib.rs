@@ -1,13 +1,24 @@
 use ext_php_rs::prelude::*;
 
 mod checkpointer;
+mod codec;
+mod credentials;
+mod encoder;
+mod error_policy;
 mod events;
+mod exception;
 mod filter;
+mod logger;
 mod stream;
 
 use checkpointer::Checkpointer;
-use events::{DeleteEvent, InsertEvent, UpdateEvent};
+use credentials::CredentialsProvider;
+use encoder::{CloudEventsEncoder, DebeziumEncoder};
+use error_policy::{DeadLetter, ErrorPolicy};
+use events::{DeleteEvent, HeartbeatEvent, InsertEvent, UpdateEvent};
+use exception::CheckpointUnavailableException;
 use filter::Filter;
+use logger::Logger;
 use stream::Stream;
 
 fn startup_function(_type: i32, _module_number: i32) -> i32 {
@@ -32,4 +43,8 @@ pub fn get_module(module: ModuleBuilder) -> ModuleBuilder {
         .class::<InsertEvent>()
         .class::<UpdateEvent>()
         .class::<DeleteEvent>()
+        .class::<HeartbeatEvent>()
+        .class::<DebeziumEncoder>()
+        .class::<CloudEventsEncoder>()
+        .class::<CheckpointUnavailableException>()
 }
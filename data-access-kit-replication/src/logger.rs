@@ -0,0 +1,78 @@
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use ext_php_rs::zend::ClassEntry;
+
+/// Rust wrapper for a PHP PSR-3 logger
+///
+/// Without a logger, warnings and errors go to stderr and everything else is dropped.
+#[derive(Debug, Default)]
+pub struct Logger {
+    php_object: Option<Zval>,
+}
+
+impl Logger {
+    /// Create a new logger wrapper from a PHP object, null means no logger
+    pub fn new(php_logger: &Zval) -> PhpResult<Self> {
+        if php_logger.is_null() {
+            return Ok(Logger::default());
+        }
+
+        let implements_interface = match (
+            php_logger.object(),
+            ClassEntry::try_find("Psr\\Log\\LoggerInterface"),
+        ) {
+            (Some(obj), Some(ce)) => obj.instance_of(ce),
+            // psr/log not installed, accept any object with a log() method
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        if !implements_interface {
+            return Err(PhpException::default(
+                "Logger must be an object implementing Psr\\Log\\LoggerInterface".into(),
+            ));
+        }
+
+        // Use shallow_clone to safely store the Zval reference
+        Ok(Logger {
+            php_object: Some(php_logger.shallow_clone()),
+        })
+    }
+
+    pub fn error(&self, message: &str, context: &[(&str, &str)]) {
+        self.log("error", message, context);
+    }
+
+    pub fn warning(&self, message: &str, context: &[(&str, &str)]) {
+        self.log("warning", message, context);
+    }
+
+    pub fn info(&self, message: &str, context: &[(&str, &str)]) {
+        self.log("info", message, context);
+    }
+
+    /// Call log(string $level, string $message, array $context) on the PHP logger
+    fn log(&self, level: &str, message: &str, context: &[(&str, &str)]) {
+        if let Some(ref logger) = self.php_object {
+            let mut context_array = ZendHashTable::new();
+            for (key, value) in context {
+                let _ = context_array.insert(*key, *value);
+            }
+
+            let params: Vec<&dyn ext_php_rs::convert::IntoZvalDyn> =
+                vec![&level, &message, &context_array];
+            if logger.try_call_method("log", params).is_ok() {
+                return;
+            }
+            // A failing logger must not hide the diagnostic, fall back to stderr
+        } else if level != "error" && level != "warning" {
+            return;
+        }
+
+        let context: Vec<String> = context
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        eprintln!("[{}] {} {}", level, message, context.join(" "));
+    }
+}
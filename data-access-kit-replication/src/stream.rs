@@ -1,17 +1,26 @@
 use ext_php_rs::prelude::*;
 use ext_php_rs::types::Zval;
 use ext_php_rs::zend::ce;
+use std::cell::{Ref, RefCell, RefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
 mod mysql;
 
-use mysql::MySQLStreamDriver;
+use crate::{CredentialsProvider, ErrorPolicy};
+use mysql::{MySQLStreamDriver, MySQLStreamOptions};
 
 pub trait StreamDriver {
     fn connect(&mut self) -> PhpResult<()>;
     fn disconnect(&mut self) -> PhpResult<()>;
     fn set_checkpointer(&mut self, checkpointer: &Zval) -> PhpResult<()>;
     fn set_filter(&mut self, filter: &Zval) -> PhpResult<()>;
+    fn set_logger(&mut self, logger: &Zval) -> PhpResult<()>;
+    fn fetch_batch(&mut self, max_events: usize, max_wait: Duration) -> PhpResult<Vec<Zval>>;
+    fn stats(&self) -> PhpResult<Zval>;
+    fn metrics(&self, name: &str) -> PhpResult<String>;
     fn current(&self) -> PhpResult<Option<Zval>>;
     fn key(&self) -> PhpResult<i32>;
     fn next(&mut self) -> PhpResult<()>;
@@ -23,33 +32,82 @@ pub trait StreamDriver {
 #[php(name = "DataAccessKit\\Replication\\Stream")]
 #[php(implements(ce = ce::iterator, stub = "Iterator"))]
 pub struct Stream {
-    driver: Box<dyn StreamDriver>,
+    driver: RefCell<Box<dyn StreamDriver>>,
+    // Owned outside the driver, so that stop() does not borrow a driver that is busy reading
+    stop_requested: Arc<AtomicBool>,
 }
 
 impl Stream {
-    fn create_driver(connection_url: &str) -> Result<Box<dyn StreamDriver>, PhpException> {
-        match Url::parse(connection_url) {
+    fn create_driver(
+        connection_url: &str,
+        options: Option<&Zval>,
+        stop_requested: Arc<AtomicBool>,
+    ) -> Result<Box<dyn StreamDriver>, PhpException> {
+        let (connection_url, hosts) = split_hosts(connection_url)
+            .map_err(|e| PhpException::default(format!("Invalid connection URL: {}", e).into()))?;
+
+        match Url::parse(&connection_url) {
             Ok(url) => match url.scheme() {
                 "mysql" => {
-                    let host = url.host_str().unwrap_or("localhost").to_string();
-
-                    let port = url.port().unwrap_or(3306);
+                    let hosts = if hosts.is_empty() {
+                        let host = url.host_str().unwrap_or("localhost").to_string();
+                        let port = url.port().unwrap_or(3306);
+                        vec![(host, port)]
+                    } else {
+                        hosts
+                    };
 
-                    let user = if url.username().is_empty() {
+                    let mut user = if url.username().is_empty() {
                         "root".to_string()
                     } else {
-                        url.username().to_string()
+                        percent_decode(url.username())
                     };
 
-                    let password = url.password().unwrap_or("").to_string();
+                    let mut password = url.password().map(percent_decode).unwrap_or_default();
+
+                    // Options array: credentials (take precedence over the URL) and error policy
+                    let mut credentials = None;
+                    let mut error_policy = ErrorPolicy::default();
+                    if let Some(options) = options.filter(|options| !options.is_null()) {
+                        let options = options.array().ok_or_else(|| {
+                            PhpException::default("Stream options must be an array".into())
+                        })?;
+
+                        for (key, value) in options.iter() {
+                            match key.to_string().as_str() {
+                                "user" => user = option_string("user", value)?,
+                                "password" => password = option_string("password", value)?,
+                                "credentials" => {
+                                    credentials = Some(CredentialsProvider::new(value)?)
+                                }
+                                "on_error" => error_policy = ErrorPolicy::new(value)?,
+                                key => {
+                                    return Err(PhpException::default(
+                                        format!("Unknown Stream option '{}'", key).into(),
+                                    ))
+                                }
+                            }
+                        }
+                    }
 
                     let server_id = url
                         .query_pairs()
                         .find(|(key, _)| key == "server_id")
                         .and_then(|(_, value)| value.parse::<u32>().ok());
 
+                    let options = MySQLStreamOptions::from_url(&url).map_err(|e| {
+                        PhpException::default(format!("Invalid connection URL: {}", e).into())
+                    })?;
+
                     Ok(Box::new(MySQLStreamDriver::new(
-                        host, port, user, password, server_id,
+                        hosts,
+                        user,
+                        password,
+                        credentials,
+                        error_policy,
+                        server_id,
+                        options,
+                        stop_requested,
                     )))
                 }
                 scheme => Err(PhpException::default(
@@ -61,49 +119,171 @@ impl Stream {
             )),
         }
     }
+
+    /// PHP code run by the driver (signal handlers, filter, checkpointer, logger) may call
+    /// back into the stream, only stop() is allowed then
+    fn driver(&self) -> PhpResult<Ref<'_, Box<dyn StreamDriver>>> {
+        self.driver.try_borrow().map_err(|_| busy())
+    }
+
+    fn driver_mut(&self) -> PhpResult<RefMut<'_, Box<dyn StreamDriver>>> {
+        self.driver.try_borrow_mut().map_err(|_| busy())
+    }
+}
+
+fn busy() -> PhpException {
+    PhpException::default("Stream is busy, only stop() can be called while it reads".into())
 }
 
 #[php_impl]
 impl Stream {
-    pub fn __construct(connection_url: String) -> PhpResult<Self> {
-        let driver = Self::create_driver(&connection_url)?;
-        Ok(Stream { driver })
+    #[php(optional = options)]
+    pub fn __construct(connection_url: String, options: Option<&Zval>) -> PhpResult<Self> {
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let driver = Self::create_driver(&connection_url, options, stop_requested.clone())?;
+        Ok(Stream {
+            driver: RefCell::new(driver),
+            stop_requested,
+        })
+    }
+
+    pub fn connect(&self) -> PhpResult<()> {
+        self.driver_mut()?.connect()
+    }
+
+    pub fn disconnect(&self) -> PhpResult<()> {
+        self.driver_mut()?.disconnect()
+    }
+
+    pub fn set_checkpointer(&self, checkpointer: &Zval) -> PhpResult<()> {
+        self.driver_mut()?.set_checkpointer(checkpointer)
     }
 
-    pub fn connect(&mut self) -> PhpResult<()> {
-        self.driver.connect()
+    pub fn set_filter(&self, filter: &Zval) -> PhpResult<()> {
+        self.driver_mut()?.set_filter(filter)
     }
 
-    pub fn disconnect(&mut self) -> PhpResult<()> {
-        self.driver.disconnect()
+    pub fn set_logger(&self, logger: &Zval) -> PhpResult<()> {
+        self.driver_mut()?.set_logger(logger)
     }
 
-    pub fn set_checkpointer(&mut self, checkpointer: &Zval) -> PhpResult<()> {
-        self.driver.set_checkpointer(checkpointer)
+    pub fn fetch_batch(&self, max_events: i64, max_wait_seconds: f64) -> PhpResult<Vec<Zval>> {
+        if max_events < 1 {
+            return Err(PhpException::default(
+                "fetchBatch() max events must be at least 1".into(),
+            ));
+        }
+        let max_wait = Duration::try_from_secs_f64(max_wait_seconds).map_err(|_| {
+            PhpException::default(
+                "fetchBatch() max wait must be a non-negative number of seconds".into(),
+            )
+        })?;
+
+        self.driver_mut()?
+            .fetch_batch(max_events as usize, max_wait)
+    }
+
+    /// Counters, replication lag and current checkpoint of the stream
+    pub fn get_stats(&self) -> PhpResult<Zval> {
+        self.driver()?.stats()
+    }
+
+    /// Stats in the OpenMetrics text format, labeled with source host, server_id and name
+    #[php(optional = name)]
+    pub fn get_metrics(&self, name: Option<String>) -> PhpResult<String> {
+        self.driver()?.metrics(name.as_deref().unwrap_or("default"))
     }
 
-    pub fn set_filter(&mut self, filter: &Zval) -> PhpResult<()> {
-        self.driver.set_filter(filter)
+    /// Finish iteration after the current event, safe to call from pcntl_signal() handlers
+    pub fn stop(&self) -> PhpResult<()> {
+        // Only an atomic store, the driver may be borrowed by the call the handler interrupted
+        self.stop_requested.store(true, Ordering::SeqCst);
+        Ok(())
     }
 
     // Iterator interface methods
     pub fn current(&self) -> PhpResult<Option<Zval>> {
-        self.driver.current()
+        self.driver()?.current()
     }
 
     pub fn key(&self) -> PhpResult<i32> {
-        self.driver.key()
+        self.driver()?.key()
     }
 
-    pub fn next(&mut self) -> PhpResult<()> {
-        self.driver.next()
+    pub fn next(&self) -> PhpResult<()> {
+        self.driver_mut()?.next()
     }
 
-    pub fn rewind(&mut self) -> PhpResult<()> {
-        self.driver.rewind()
+    pub fn rewind(&self) -> PhpResult<()> {
+        self.driver_mut()?.rewind()
     }
 
     pub fn valid(&self) -> PhpResult<bool> {
-        self.driver.valid()
+        self.driver()?.valid()
     }
 }
+
+fn percent_decode(value: &str) -> String {
+    percent_encoding::percent_decode_str(value)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Take a comma separated host list out of the URL, the url crate parses a single host only
+///
+/// Returns the URL with the first host in place of the list and the hosts with their ports,
+/// no hosts when the URL has a single one.
+fn split_hosts(connection_url: &str) -> Result<(String, Vec<(String, u16)>), String> {
+    let Some(authority_start) = connection_url.find("://").map(|index| index + 3) else {
+        return Ok((connection_url.to_string(), Vec::new()));
+    };
+    let authority_end = connection_url[authority_start..]
+        .find(['/', '?', '#'])
+        .map_or(connection_url.len(), |index| authority_start + index);
+    let hosts_start = connection_url[authority_start..authority_end]
+        .rfind('@')
+        .map_or(authority_start, |index| authority_start + index + 1);
+
+    let host_list = &connection_url[hosts_start..authority_end];
+    if !host_list.contains(',') {
+        return Ok((connection_url.to_string(), Vec::new()));
+    }
+
+    let mut hosts = Vec::new();
+    for host_port in host_list.split(',') {
+        // IPv6 addresses are enclosed in brackets, the port follows the closing one
+        let port_separator = match host_port.rfind(']') {
+            Some(bracket) => host_port[bracket..].find(':').map(|index| bracket + index),
+            None => host_port.rfind(':'),
+        };
+        let (host, port) = match port_separator {
+            Some(index) => {
+                let port = &host_port[index + 1..];
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| format!("invalid port '{}' of host '{}'", port, host_port))?;
+                (&host_port[..index], port)
+            }
+            None => (host_port, 3306),
+        };
+        if host.is_empty() {
+            return Err(format!("empty host in '{}'", host_list));
+        }
+        hosts.push((host.to_string(), port));
+    }
+
+    let connection_url = format!(
+        "{}{}{}",
+        &connection_url[..hosts_start],
+        host_list.split(',').next().unwrap_or_default(),
+        &connection_url[authority_end..]
+    );
+
+    Ok((connection_url, hosts))
+}
+
+fn option_string(key: &str, value: &Zval) -> Result<String, PhpException> {
+    value.string().ok_or_else(|| {
+        PhpException::default(format!("Stream option '{}' must be a string", key).into())
+    })
+}
@@ -1,12 +1,13 @@
 use super::StreamDriver;
-use crate::{Checkpointer, Filter};
+use crate::{
+    codec, CheckpointUnavailableException, Checkpointer, CredentialsProvider, DeadLetter,
+    ErrorPolicy, Filter, Logger,
+};
 use ext_php_rs::prelude::*;
-use ext_php_rs::types::Zval;
+use ext_php_rs::types::{ZendCallable, ZendHashTable, Zval};
 use ext_php_rs::zend;
-use mysql_async::{OptsBuilder, Pool};
+use mysql_async::{Conn, DriverError, IoError, OptsBuilder, Pool, SslOpts};
 use mysql_binlog_connector_rust::{
-    binlog_client::BinlogClient,
-    binlog_stream::BinlogStream,
     column::column_value::ColumnValue,
     event::{
         event_data::EventData, event_header::EventHeader, row_event::RowEvent,
@@ -14,10 +15,32 @@ use mysql_binlog_connector_rust::{
     },
 };
 use std::collections::{HashMap, VecDeque};
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::LazyLock;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::runtime::Runtime;
+use url::Url;
+
+mod binlog_connection;
+mod gtid_set;
+mod loop_prevention;
+mod mariadb_gtid;
+mod options;
+mod reader;
+mod start_position;
+mod stats;
+mod stop_condition;
+
+use binlog_connection::{BinlogConnection, BinlogConnectionOpts, BinlogEvent, DumpPosition};
+use gtid_set::GtidSet;
+use loop_prevention::LoopPrevention;
+use mariadb_gtid::{MariaDbGtid, MariaDbGtidPosition};
+pub use options::MySQLStreamOptions;
+use options::{CheckpointFormat, SslMode};
+use reader::BinlogReader;
+use start_position::StartPosition;
+use stats::StreamStats;
+use stop_condition::StopCondition;
 
 macro_rules! with_runtime_block_on {
     ($self:ident, $async_block:expr) => {{
@@ -29,6 +52,49 @@ macro_rules! with_runtime_block_on {
     }};
 }
 
+/// Add the GTID of the transaction being read to current_gtid once it has committed
+///
+/// Until then checkpoints exclude the transaction, so that resuming reads it again in full.
+/// A macro rather than a method, the read loop holds a borrow of the binlog reader.
+macro_rules! commit_transaction_gtid {
+    ($self:ident) => {{
+        if let Some(gtid) = $self.uncommitted_gtid.take() {
+            if $self.is_mariadb {
+                if let (Some(gtid), Some(ref mut gtid_position)) =
+                    (MariaDbGtid::parse(&gtid), &mut $self.mariadb_gtid_position)
+                {
+                    gtid_position.advance(gtid);
+                    $self.current_gtid = Some(gtid_position.to_string());
+                }
+            } else {
+                let mut gtid_set = $self
+                    .gtid_set
+                    .take()
+                    .or_else(|| $self.current_gtid.as_deref().and_then(GtidSet::parse))
+                    .unwrap_or_default();
+                if gtid_set.add(&gtid) {
+                    $self.current_gtid = Some(gtid_set.to_string());
+                }
+                $self.gtid_set = Some(gtid_set);
+            }
+        }
+    }};
+}
+
+/// Origin and position of a binlog rows event, shared by all rows it carries
+struct EventSource {
+    timestamp: i64,
+    checkpoint: String,
+    server_id: u32,
+    binlog_file: String,
+    binlog_position: u64,
+    gtid: Option<String>,
+    query: Option<String>,
+}
+
+// How often a blocking read wakes up to run PHP signal handlers
+const SIGNAL_DISPATCH_INTERVAL: Duration = Duration::from_millis(100);
+
 static NEXT_SERVER_ID: LazyLock<AtomicU32> = LazyLock::new(|| {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -39,16 +105,25 @@ static NEXT_SERVER_ID: LazyLock<AtomicU32> = LazyLock::new(|| {
 });
 
 pub struct MySQLStreamDriver {
-    host: String,
+    hosts: Vec<(String, u16)>, // Failover candidates, in order of preference
+    host: String,              // Host of the current connection, one of hosts
     port: u16,
     user: String,
     password: String,
+    credentials: Option<CredentialsProvider>, // Refreshes user and password on connect
     server_id: Option<u32>,
+    options: MySQLStreamOptions,
+    ssl_opts: Option<SslOpts>, // TLS of the connections to the current host, None when plain text
     position: u64,
     pool: Option<Pool>,
-    binlog_client: Option<BinlogClient>,
-    binlog_stream: Option<BinlogStream>,
+    binlog_opts: Option<BinlogConnectionOpts>, // What the open binlog connection was opened with
+    binlog_reader: Option<BinlogReader>,
     current_gtid: Option<String>,
+    transaction_gtid: Option<String>, // GTID of the transaction currently being read
+    uncommitted_gtid: Option<String>, // transaction_gtid until it commits, not in current_gtid yet
+    rows_query: Option<String>,       // Statement text of the rows events currently being read
+    gtid_set: Option<GtidSet>,        // current_gtid parsed, tracked from MySQL GTID events
+    mariadb_gtid_position: Option<MariaDbGtidPosition>, // Tracked from MariaDB GTID events
     current_binlog_file: Option<String>,
     current_binlog_position: Option<u64>,
     is_mariadb: bool,
@@ -56,32 +131,58 @@ pub struct MySQLStreamDriver {
     current_event: Option<Zval>,
     event_queue: VecDeque<Zval>, // Queue for buffering multi-row events
     event_iterator_started: bool,
+    idle: bool, // Last read ended by idle timeout, iteration resumes on next()/rewind()
+    skip_before: Option<i64>, // Skip rows events older than this timestamp (start=timestamp:)
+    stop_at: Option<StopCondition>, // Bound of the replay, Current resolved to a position
+    loop_prevention: LoopPrevention, // Drops changes the consumer wrote back itself
+    emitted: u64, // Row events handed to PHP since start, for max_events
+    stop_requested: Arc<AtomicBool>, // Set by Stream::stop(), possibly from a signal handler
+    dispatch_signals: bool, // pcntl without async signals, run signal handlers while blocked
+    last_checkpoint: Option<String>, // Checkpoint of the last event handed to PHP
+    connection_lost: bool, // Last read failed on the binlog connection
     connected: bool,
     table_map: HashMap<u64, TableMapEvent>,
     checkpointer: Option<Checkpointer>,
     filter: Option<Filter>,
+    logger: Logger,
+    error_policy: ErrorPolicy,
+    stats: StreamStats,
     runtime: Option<Runtime>,
 }
 
 impl MySQLStreamDriver {
     pub fn new(
-        host: String,
-        port: u16,
+        hosts: Vec<(String, u16)>,
         user: String,
         password: String,
+        credentials: Option<CredentialsProvider>,
+        error_policy: ErrorPolicy,
         server_id: Option<u32>,
+        options: MySQLStreamOptions,
+        stop_requested: Arc<AtomicBool>,
     ) -> Self {
+        let (host, port) = hosts[0].clone();
+        let loop_prevention = options.loop_prevention.clone();
         MySQLStreamDriver {
-            host: host.clone(),
+            hosts,
+            host,
             port,
             user: user.clone(),
             password,
+            credentials,
             server_id,
+            options,
+            ssl_opts: None,
             position: 0,
             pool: None,
-            binlog_client: None,
-            binlog_stream: None,
+            binlog_opts: None,
+            binlog_reader: None,
             current_gtid: None,
+            transaction_gtid: None,
+            uncommitted_gtid: None,
+            rows_query: None,
+            gtid_set: None,
+            mariadb_gtid_position: None,
             current_binlog_file: None,
             current_binlog_position: None,
             is_mariadb: false,
@@ -89,10 +190,22 @@ impl MySQLStreamDriver {
             current_event: None,
             event_queue: VecDeque::new(),
             event_iterator_started: false,
+            idle: false,
+            skip_before: None,
+            stop_at: None,
+            loop_prevention,
+            emitted: 0,
+            stop_requested,
+            dispatch_signals: false,
+            last_checkpoint: None,
+            connection_lost: false,
             connected: false,
             table_map: HashMap::new(),
             checkpointer: None,
             filter: None,
+            logger: Logger::default(),
+            error_policy,
+            stats: StreamStats::default(),
             runtime: None,
         }
     }
@@ -173,6 +286,32 @@ impl MySQLStreamDriver {
 
         self.is_mariadb = version.to_lowercase().contains("mariadb");
 
+        // A replica writes changes replicated from its source to its own binlog only with
+        // log_replica_updates, without it the stream would silently miss them
+        if self.options.require_log_replica_updates && self.is_replica(&mut conn).await? {
+            let log_replica_updates: Vec<(String, String)> =
+                mysql_async::prelude::Queryable::query(
+                    &mut conn,
+                    "SHOW VARIABLES WHERE Variable_name IN \
+                     ('log_replica_updates', 'log_slave_updates')",
+                )
+                .await
+                .map_err(|e| format!("Failed to query log_replica_updates: {}", e))?;
+
+            if !log_replica_updates
+                .iter()
+                .any(|(_, value)| value.eq_ignore_ascii_case("ON"))
+            {
+                return Err(
+                    "log_replica_updates must be ON when streaming from a replica, \
+                     changes replicated from its source are not in its binlog \
+                     (set require_log_replica_updates=false to stream changes made on the \
+                     replica only)"
+                        .to_string(),
+                );
+            }
+        }
+
         if !self.is_mariadb {
             // MySQL - check GTID configuration
             let gtid_mode: String = mysql_async::prelude::Queryable::query_first(
@@ -184,20 +323,67 @@ impl MySQLStreamDriver {
             .map(|row: (String, String)| row.1)
             .unwrap_or_default();
 
-            if gtid_mode.to_uppercase() != "ON" {
-                return Err(format!("gtid_mode must be ON, got: {}", gtid_mode));
+            // ON_PERMISSIVE still writes anonymous transactions GTID checkpoints would miss
+            let gtid_enabled = gtid_mode.to_uppercase() == "ON";
+            match self.options.checkpoint_format {
+                CheckpointFormat::Gtid if !gtid_enabled => {
+                    return Err(format!(
+                        "gtid_mode must be ON for checkpoint_format=gtid, got: {}",
+                        gtid_mode
+                    ));
+                }
+                CheckpointFormat::Auto if !gtid_enabled && self.options.require_gtid => {
+                    return Err(format!(
+                        "gtid_mode must be ON, got: {} (set require_gtid=false to use binlog \
+                         file/position checkpoints)",
+                        gtid_mode
+                    ));
+                }
+                _ => {}
             }
 
-            // MySQL with GTID enabled - use GTID checkpointing
-            self.use_gtid_checkpoints = true;
+            // MySQL with GTID enabled - use GTID checkpointing unless asked for file/position
+            self.use_gtid_checkpoints =
+                gtid_enabled && self.options.checkpoint_format != CheckpointFormat::File;
         } else {
-            // MariaDB - always use binlog file/position checkpointing (per spec)
-            self.use_gtid_checkpoints = false;
+            // MariaDB - binlog file/position checkpointing unless asked for GTIDs
+            self.use_gtid_checkpoints = self.options.checkpoint_format == CheckpointFormat::Gtid;
         }
 
         Ok(())
     }
 
+    /// Whether the server replicates from a source
+    async fn is_replica(&self, conn: &mut mysql_async::Conn) -> Result<bool, String> {
+        use mysql_async::prelude::*;
+
+        // SHOW SLAVE STATUS is gone from MySQL 8.4, SHOW REPLICA STATUS is missing before 8.0.22
+        let query = if self.is_mariadb {
+            "SHOW ALL SLAVES STATUS"
+        } else {
+            "SHOW REPLICA STATUS"
+        };
+        let status: Result<Vec<mysql_async::Row>, mysql_async::Error> =
+            match conn.query(query).await {
+                Err(e) if !is_access_denied(&e) => conn.query("SHOW SLAVE STATUS").await,
+                status => status,
+            };
+
+        match status {
+            Ok(status) => Ok(!status.is_empty()),
+            // Needs REPLICATION CLIENT (REPLICA MONITOR on MariaDB), which streams reading
+            // from a primary don't otherwise need
+            Err(e) if is_access_denied(&e) => {
+                self.logger.warning(
+                    "Cannot check whether the server is a replica, assuming it is not",
+                    &[("error", &e.to_string())],
+                );
+                Ok(false)
+            }
+            Err(e) => Err(format!("Failed to query replica status: {}", e)),
+        }
+    }
+
     async fn get_current_gtid(&self, pool: &Pool) -> Result<String, String> {
         let mut conn = pool
             .get_conn()
@@ -273,8 +459,8 @@ impl MySQLStreamDriver {
     }
 
     fn generate_checkpoint(&self, header: &EventHeader) -> String {
-        if self.use_gtid_checkpoints && !self.is_mariadb {
-            // MySQL with GTID - use "gtid:" prefix
+        if self.use_gtid_checkpoints {
+            // MySQL GTID set or MariaDB GTID position - use "gtid:" prefix
             if let Some(ref gtid) = self.current_gtid {
                 format!("gtid:{}", gtid)
             } else {
@@ -282,22 +468,14 @@ impl MySQLStreamDriver {
                 self.generate_file_position_checkpoint(header)
             }
         } else {
-            // MariaDB or MySQL without GTID - use "file:" prefix
+            // File/position checkpoints - use "file:" prefix
             self.generate_file_position_checkpoint(header)
         }
     }
 
     fn generate_file_position_checkpoint(&self, header: &EventHeader) -> String {
-        if let Some(ref binlog_client) = self.binlog_client {
-            // Use the current binlog file and position from the client
-            format!(
-                "file:{}:{}",
-                binlog_client.binlog_filename, header.next_event_position
-            )
-        } else if let (Some(ref file), Some(_pos)) =
-            (&self.current_binlog_file, &self.current_binlog_position)
-        {
-            // Use stored file and position from header
+        if let Some(ref file) = self.current_binlog_file {
+            // Use the binlog file tracked from rotate events
             format!("file:{}:{}", file, header.next_event_position)
         } else {
             // Emergency fallback - use position from header
@@ -309,23 +487,422 @@ impl MySQLStreamDriver {
         }
     }
 
+    /// Collect origin and position of the event described by the header
+    fn event_source(&self, header: &EventHeader) -> EventSource {
+        EventSource {
+            timestamp: header.timestamp as i64,
+            checkpoint: self.generate_checkpoint(header),
+            server_id: header.server_id,
+            binlog_file: self.current_binlog_file.clone().unwrap_or_default(),
+            // Header carries the end position, the event starts event_length bytes before it
+            binlog_position: (header.next_event_position as u64)
+                .saturating_sub(header.event_length as u64),
+            gtid: self.transaction_gtid.clone(),
+            query: self.rows_query.clone(),
+        }
+    }
+
     /// Save the current checkpoint using the configured checkpointer
-    fn save_current_checkpoint(&self, header: &EventHeader) -> PhpResult<()> {
+    fn save_current_checkpoint(&mut self, header: &EventHeader) -> PhpResult<()> {
         if let Some(ref checkpointer) = self.checkpointer {
             let checkpoint = self.generate_checkpoint(header);
             checkpointer.save_checkpoint(&checkpoint)?;
+            self.stats.checkpoint_saves += 1;
         }
         // If no checkpointer is configured, silently continue
         Ok(())
     }
 
-    /// Load checkpoint from checkpointer if available and apply it
-    fn load_checkpoint_if_available(&mut self) -> PhpResult<()> {
+    /// Load checkpoint from checkpointer if available and apply it, returns whether it was
+    fn load_checkpoint_if_available(&mut self) -> PhpResult<Option<String>> {
         if let Some(ref checkpointer) = self.checkpointer {
             if let Some(checkpoint_str) = checkpointer.load_last_checkpoint()? {
                 self.apply_checkpoint(&checkpoint_str)?;
+                return Ok(Some(checkpoint_str));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Make sure the server still has the binlog after the checkpoint
+    ///
+    /// Returns where to start instead when on_checkpoint_unavailable allows a fallback.
+    fn validate_checkpoint(&mut self, checkpoint: &str) -> PhpResult<Option<StartPosition>> {
+        let pool = self
+            .pool
+            .clone()
+            .ok_or_else(|| PhpException::default("Stream not connected".into()))?;
+
+        // MySQL streams without GTIDs when gtid_mode is not ON (require_gtid=false)
+        let unavailable =
+            if checkpoint.starts_with("gtid:") && !self.is_mariadb && !self.use_gtid_checkpoints {
+                Some(
+                    "the stream uses binlog file/position checkpoints, gtid_mode is not ON \
+                 or checkpoint_format is file"
+                        .to_string(),
+                )
+            } else {
+                with_runtime_block_on!(self, async {
+                    start_position::checkpoint_unavailable(&pool, checkpoint, self.is_mariadb).await
+                })
+                .map_err(|e| {
+                    PhpException::default(format!("Failed to validate checkpoint: {}", e).into())
+                })?
+            };
+
+        let Some(reason) = unavailable else {
+            return Ok(None);
+        };
+
+        // Another host may still have the transactions after a GTID checkpoint
+        if checkpoint.starts_with("gtid:")
+            && self.hosts.len() > 1
+            && self.fail_over(checkpoint, false)?
+        {
+            return Ok(None);
+        }
+
+        match self.options.on_checkpoint_unavailable {
+            None => Err(PhpException::from_class::<CheckpointUnavailableException>(
+                format!(
+                    "Checkpoint '{}' is not available: {}. Reset the checkpoint, or set \
+                 on_checkpoint_unavailable to earliest or latest to start over",
+                    checkpoint, reason
+                ),
+            )),
+            Some(ref fallback) => {
+                self.logger.warning(
+                    "Checkpoint not available, starting over",
+                    &[
+                        ("checkpoint", checkpoint),
+                        ("reason", &reason),
+                        (
+                            "start",
+                            if *fallback == StartPosition::Earliest {
+                                "earliest"
+                            } else {
+                                "latest"
+                            },
+                        ),
+                    ],
+                );
+                Ok(Some(fallback.clone()))
             }
         }
+    }
+
+    /// Position the stream according to the start option, used when there is no checkpoint
+    async fn apply_start_position(&mut self, start: StartPosition) -> Result<(), String> {
+        let pool = match self.pool {
+            Some(ref pool) => pool.clone(),
+            None => return Err("Not connected".to_string()),
+        };
+
+        match start {
+            // Only used as fallback, the unavailable checkpoint replaced the position connect() found
+            StartPosition::Latest => {
+                if self.use_gtid_checkpoints && !self.is_mariadb {
+                    self.current_gtid = Some(self.get_current_gtid(&pool).await?);
+                } else if self.is_mariadb {
+                    self.current_gtid = None;
+                }
+                self.get_current_binlog_position(&pool).await?;
+                Ok(())
+            }
+            // Applied synchronously by start()
+            StartPosition::Checkpoint(_) => Ok(()),
+            StartPosition::Earliest => {
+                let files = start_position::binlog_files(&pool).await?;
+                self.start_at_file(&pool, &files[0]).await
+            }
+            StartPosition::Timestamp(timestamp) => {
+                let files = start_position::binlog_files(&pool).await?;
+                let timeout = self.options.read_timeout;
+                let opts = BinlogConnectionOpts {
+                    opts: self.connection_opts(),
+                    server_id: NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed),
+                    position: DumpPosition::File(String::new(), 4),
+                    heartbeat_interval: timeout / 2,
+                    read_timeout: timeout,
+                };
+
+                // Binary search for the last file started at or before the timestamp
+                let (mut low, mut high) = (0, files.len());
+                while high - low > 1 {
+                    let middle = (low + high) / 2;
+                    let started_at = start_position::binlog_start_timestamp(&opts, &files[middle])
+                        .await
+                        .map_err(|e| self.redact(e))?;
+                    if started_at <= timestamp {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+
+                self.start_at_file(&pool, &files[low]).await?;
+                // The file also holds events older than the timestamp
+                self.skip_before = Some(timestamp);
+                Ok(())
+            }
+        }
+    }
+
+    /// Connect to the host at the index, validating its configuration
+    ///
+    /// The inner error describes why the host cannot be used, so that the next one can be tried.
+    fn connect_host(&mut self, index: usize) -> PhpResult<Result<(), String>> {
+        (self.host, self.port) = self.hosts[index].clone();
+
+        let connected: Result<(), String> = with_runtime_block_on!(self, async {
+            let connect_timeout = self.options.connect_timeout;
+            self.ssl_opts = self.options.ssl.to_ssl_opts();
+
+            // PREFERRED falls back to an unencrypted connection when TLS cannot be negotiated,
+            // any other failure is reported by the validation below
+            if self.options.ssl.mode == SslMode::Preferred {
+                let probe = Conn::new(self.connection_opts());
+                match with_connect_timeout(connect_timeout, async { Ok(probe.await) }).await {
+                    Ok(Ok(conn)) => {
+                        let _ = conn.disconnect().await;
+                    }
+                    Ok(Err(ref e)) if is_tls_error(e) => self.ssl_opts = None,
+                    _ => {}
+                }
+            }
+
+            // Validate MySQL configuration (this also sets is_mariadb and use_gtid_checkpoints)
+            let pool = Pool::new(self.connection_opts());
+            with_connect_timeout(connect_timeout, self.validate_mysql_config(&pool))
+                .await
+                .map_err(|e| self.redact(format!("MySQL configuration invalid: {}", e)))?;
+
+            // Get current GTID position for binlog streaming (only for MySQL with GTID)
+            // Only set if not already set by checkpoint
+            if self.use_gtid_checkpoints && !self.is_mariadb && self.current_gtid.is_none() {
+                let current_gtid = self
+                    .get_current_gtid(&pool)
+                    .await
+                    .map_err(|e| format!("Failed to get GTID: {}", e))?;
+                self.current_gtid = Some(current_gtid);
+            }
+
+            // Always get binlog file/position for checkpointing if not set by checkpoint
+            if self.current_binlog_file.is_none() || self.current_binlog_position.is_none() {
+                let (binlog_file, binlog_position) = self
+                    .get_current_binlog_position(&pool)
+                    .await
+                    .map_err(|e| format!("Failed to get binlog position: {}", e))?;
+
+                // Store for checkpoint generation only if not already set
+                if self.current_binlog_file.is_none() {
+                    self.current_binlog_file = Some(binlog_file);
+                }
+                if self.current_binlog_position.is_none() {
+                    self.current_binlog_position = Some(binlog_position);
+                }
+            }
+            self.pool = Some(pool);
+            self.connected = true;
+
+            Ok(())
+        });
+
+        Ok(connected)
+    }
+
+    /// Switch to a host that has executed every transaction of the GTID checkpoint
+    ///
+    /// The current host is tried first when retry_current is set, the other hosts in order
+    /// after it. Returns false, connected to the original host again, when none can continue.
+    fn fail_over(&mut self, checkpoint: &str, retry_current: bool) -> PhpResult<bool> {
+        let original = self
+            .hosts
+            .iter()
+            .position(|host| *host == (self.host.clone(), self.port))
+            .unwrap_or(0);
+
+        let first_attempt = if retry_current { 0 } else { 1 };
+        for attempt in first_attempt..=self.hosts.len() {
+            let index = (original + attempt) % self.hosts.len();
+            let host = format!("{}:{}", self.hosts[index].0, self.hosts[index].1);
+
+            self.pool = None;
+            self.binlog_reader = None;
+            self.binlog_opts = None;
+            self.connected = false;
+
+            let connected = self.connect_host(index)?;
+            // Connecting filled in the position of the new host, the checkpoint decides
+            self.apply_checkpoint(checkpoint)?;
+
+            if attempt == self.hosts.len() {
+                connected.map_err(|e| PhpException::default(e.into()))?;
+                return Ok(false);
+            }
+
+            let unavailable = match connected {
+                Ok(()) => {
+                    let pool = self
+                        .pool
+                        .clone()
+                        .ok_or_else(|| PhpException::default("Stream not connected".into()))?;
+                    with_runtime_block_on!(self, async {
+                        start_position::checkpoint_unavailable(&pool, checkpoint, self.is_mariadb)
+                            .await
+                    })
+                    .unwrap_or_else(Some)
+                }
+                Err(e) => Some(e),
+            };
+
+            match unavailable {
+                None => {
+                    self.logger.warning(
+                        if index == original {
+                            "Reconnected"
+                        } else {
+                            "Failed over"
+                        },
+                        &[("host", &host), ("checkpoint", checkpoint)],
+                    );
+                    return Ok(true);
+                }
+                Some(reason) => self.logger.warning(
+                    "Host cannot continue from checkpoint",
+                    &[
+                        ("host", &host),
+                        ("checkpoint", checkpoint),
+                        ("reason", &reason),
+                    ],
+                ),
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Read the next event, failing over to another host when the binlog connection is lost
+    fn read_next_event(&mut self, deadline: Option<Instant>) -> PhpResult<()> {
+        self.connection_lost = false;
+        let error = match self.fetch_next_event(deadline) {
+            Err(error) if self.connection_lost && self.hosts.len() > 1 => error,
+            result => return result,
+        };
+
+        // Only GTIDs are the same on every host. The GTIDs of committed transactions exclude
+        // the one the connection was lost in, so that it is read again from its start.
+        let checkpoint = match self
+            .current_gtid
+            .clone()
+            .filter(|_| self.use_gtid_checkpoints)
+        {
+            Some(gtid) => format!("gtid:{}", gtid),
+            None => return Err(error),
+        };
+
+        self.logger.warning(
+            "Binlog connection lost",
+            &[("host", &format!("{}:{}", self.host, self.port))],
+        );
+        // The connection may have dropped for a reason on the way, the host itself can still be fine
+        if !self.fail_over(&checkpoint, true)? {
+            return Err(error);
+        }
+
+        self.transaction_gtid = None;
+        self.rows_query = None;
+        self.gtid_set = None;
+        self.uncommitted_gtid = None;
+        self.mariadb_gtid_position = None;
+        self.table_map.clear();
+        self.loop_prevention.end_transaction();
+        with_runtime_block_on!(self, async { self.initialize_binlog_client().await })?;
+        self.stats.reconnects += 1;
+
+        self.fetch_next_event(deadline)
+    }
+
+    /// Resolve the until option, stopping right away when the start is already past it
+    fn resolve_stop_condition(&mut self) -> PhpResult<()> {
+        self.emitted = 0;
+        // GTID starts leave file and position at the server's current position
+        let gtid_start = self.use_gtid_checkpoints && !self.is_mariadb;
+        self.stop_at = match self.options.until {
+            // The server skips transactions already read, so the file and position of the
+            // last event sent may stay short of the current one, the GTIDs do not
+            Some(StopCondition::Current) if gtid_start => {
+                let pool = self
+                    .pool
+                    .clone()
+                    .ok_or_else(|| PhpException::default("Stream not connected".into()))?;
+                let current =
+                    with_runtime_block_on!(self, async { self.get_current_gtid(&pool).await })
+                        .map_err(|e| {
+                            PhpException::default(
+                                format!("Failed to get GTID position: {}", e).into(),
+                            )
+                        })?;
+                match GtidSet::parse(&current) {
+                    Some(gtid_set) => Some(StopCondition::Gtid(gtid_set)),
+                    // Nothing written yet, nothing to replay
+                    None => {
+                        self.stop_requested.store(true, Ordering::SeqCst);
+                        None
+                    }
+                }
+            }
+            Some(StopCondition::Gtid(_)) if !gtid_start => {
+                return Err(PhpException::default(
+                    "until=gtid: needs MySQL with GTID checkpoints".into(),
+                ));
+            }
+            Some(StopCondition::Current) => {
+                let pool = self
+                    .pool
+                    .clone()
+                    .ok_or_else(|| PhpException::default("Stream not connected".into()))?;
+                // The query records the position as the start, keep the one already chosen
+                let start_file = self.current_binlog_file.clone();
+                let start_position = self.current_binlog_position;
+                let current = with_runtime_block_on!(self, async {
+                    self.get_current_binlog_position(&pool).await
+                });
+                self.current_binlog_file = start_file;
+                self.current_binlog_position = start_position;
+
+                let (file, position) = current.map_err(|e| {
+                    PhpException::default(format!("Failed to get binlog position: {}", e).into())
+                })?;
+                Some(StopCondition::Position(file, position))
+            }
+            ref until => until.clone(),
+        };
+
+        if let (Some(ref stop_at), Some(ref file), Some(position)) = (
+            &self.stop_at,
+            &self.current_binlog_file,
+            self.current_binlog_position,
+        ) {
+            if !gtid_start && stop_at.reached_at_position(file, position) {
+                self.stop_requested.store(true, Ordering::SeqCst);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Start reading at the beginning of a binlog file
+    async fn start_at_file(&mut self, pool: &Pool, file: &str) -> Result<(), String> {
+        if self.use_gtid_checkpoints && !self.is_mariadb {
+            // GTID streaming skips transactions in the set, i.e. those before the file
+            self.current_gtid = Some(start_position::previous_gtids(pool, file).await?);
+        } else if self.is_mariadb {
+            // The GTID position is looked up at the first transaction
+            self.current_gtid = None;
+        }
+        self.current_binlog_file = Some(file.to_string());
+        self.current_binlog_position = Some(4);
         Ok(())
     }
 
@@ -386,80 +963,384 @@ impl MySQLStreamDriver {
         Ok(())
     }
 
+    /// Remove the password from a message before it reaches PHP
+    fn redact(&self, message: String) -> String {
+        redact_secret(&self.password, message)
+    }
+
+    /// Connection options of the metadata pool and the binlog connection
+    fn connection_opts(&self) -> OptsBuilder {
+        // For replication, we don't connect to a specific database
+        // The replication user needs REPLICATION SLAVE/CLIENT privileges, not database access
+        OptsBuilder::default()
+            .ip_or_hostname(&self.host)
+            .tcp_port(self.port)
+            .user(Some(&self.user))
+            .pass(Some(&self.password))
+            .tcp_keepalive(
+                self.options
+                    .tcp_keepalive
+                    .map(|interval| interval.as_millis().min(u32::MAX as u128) as u32),
+            )
+            .ssl_opts(self.ssl_opts.clone())
+            .socket(self.options.socket.clone())
+    }
+
     async fn initialize_binlog_client(&mut self) -> PhpResult<()> {
-        let connection_url = format!(
-            "mysql://{}:{}@{}:{}",
-            self.user, self.password, self.host, self.port
-        );
+        // A MariaDB GTID checkpoint leaves the file unset, the server looks up where it continues
+        let mariadb_gtid_start = match self.current_gtid {
+            Some(ref gtid_pos) if self.is_mariadb && self.current_binlog_file.is_none() => {
+                Some(MariaDbGtidPosition::parse(gtid_pos).ok_or_else(|| {
+                    PhpException::default(
+                        format!("Invalid MariaDB GTID position '{}'", gtid_pos).into(),
+                    )
+                })?)
+            }
+            _ => None,
+        };
 
-        let mut binlog_client = if !self.is_mariadb && self.use_gtid_checkpoints {
-            // MySQL with GTID - use GTID mode
-            let gtid_set = self.current_gtid.clone().unwrap_or_default();
-            BinlogClient {
-                url: connection_url,
-                binlog_filename: "".to_string(),
-                binlog_position: 4,
-                server_id: self
-                    .server_id
-                    .unwrap_or_else(|| NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed))
-                    as u64,
-                gtid_enabled: true,
-                gtid_set,
-                heartbeat_interval_secs: 30,
-                timeout_secs: 60,
+        let position = match (
+            mariadb_gtid_start,
+            self.current_gtid.as_deref().and_then(GtidSet::parse),
+        ) {
+            (Some(gtid_position), _) => {
+                // GTID events advance the position from the checkpoint on
+                self.mariadb_gtid_position = Some(gtid_position.clone());
+                DumpPosition::MariaDbGtid(gtid_position)
             }
-        } else {
-            // MariaDB (always) or MySQL without GTID - use binlog file/position
-            let binlog_file = self
-                .current_binlog_file
-                .clone()
-                .unwrap_or_else(|| String::new());
-            let binlog_position = self.current_binlog_position.unwrap_or(4);
-
-            BinlogClient {
-                url: connection_url,
-                binlog_filename: binlog_file,
-                binlog_position: binlog_position as u32,
-                server_id: self
-                    .server_id
-                    .unwrap_or_else(|| NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed))
-                    as u64,
-                gtid_enabled: false,
-                gtid_set: String::new(), // Explicitly empty for MariaDB
-                heartbeat_interval_secs: 30,
-                timeout_secs: 60,
+            // MySQL with GTID - stream every transaction not in the set
+            (None, Some(gtid_set)) if !self.is_mariadb && self.use_gtid_checkpoints => {
+                DumpPosition::Gtid(gtid_set)
             }
+            // MariaDB or MySQL without GTID - use binlog file/position
+            _ => DumpPosition::File(
+                self.current_binlog_file.clone().unwrap_or_default(),
+                self.current_binlog_position.unwrap_or(4),
+            ),
+        };
+        let binlog_opts = BinlogConnectionOpts {
+            opts: self.connection_opts(),
+            server_id: self
+                .server_id
+                .unwrap_or_else(|| NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed)),
+            position,
+            heartbeat_interval: self.options.heartbeat_interval,
+            read_timeout: self.options.read_timeout,
         };
 
-        // Connect to binlog stream
-        let binlog_stream = binlog_client.connect().await.map_err(|e| {
-            PhpException::default(format!("Failed to connect to binlog: {}", e).into())
+        // Connect to binlog stream, the prefetch thread connects on its own runtime
+        let connect_timeout = self.options.connect_timeout;
+        let binlog_reader = match self.options.prefetch {
+            Some(buffer_size) => {
+                BinlogReader::prefetch(binlog_opts.clone(), buffer_size, connect_timeout).await
+            }
+            None => with_connect_timeout(connect_timeout, BinlogConnection::open(&binlog_opts))
+                .await
+                .map(BinlogReader::direct),
+        }
+        .map_err(|e| {
+            PhpException::default(
+                self.redact(format!("Failed to connect to binlog: {}", e))
+                    .into(),
+            )
         })?;
 
-        self.binlog_stream = Some(binlog_stream);
-        self.binlog_client = Some(binlog_client);
+        let (binlog_file, binlog_position, gtid_set) = match binlog_opts.position {
+            DumpPosition::Gtid(ref gtid_set) => (String::new(), 4, gtid_set.to_string()),
+            DumpPosition::MariaDbGtid(ref gtid_position) => {
+                (String::new(), 4, gtid_position.to_string())
+            }
+            DumpPosition::File(ref file, position) => (file.clone(), position, String::new()),
+        };
+        self.logger.info(
+            "Binlog stream opened",
+            &[
+                ("binlog_file", &binlog_file),
+                ("binlog_position", &binlog_position.to_string()),
+                ("gtid_set", &gtid_set),
+            ],
+        );
+
+        self.binlog_reader = Some(binlog_reader);
+        self.binlog_opts = Some(binlog_opts);
+        Ok(())
+    }
+
+    /// Read binlog events until a rows event passes the filter and queue its rows
+    ///
+    /// With a deadline, returns with an empty queue when no rows event arrives in time.
+    fn fetch_next_event(&mut self, deadline: Option<Instant>) -> PhpResult<()> {
+        // Keep reading while rows events end up rejected by the error policy
+        while self.read_rows_event(deadline)? {}
         Ok(())
     }
 
-    fn fetch_next_event(&mut self) -> PhpResult<()> {
+    /// Read up to the next rows event and queue its rows
+    ///
+    /// Returns true when a rows event was read but none of its rows could be queued.
+    fn read_rows_event(&mut self, deadline: Option<Instant>) -> PhpResult<bool> {
         // Structure to hold event data for processing outside the async block
         enum EventToProcess {
             Insert(EventHeader, TableMapEvent, Vec<RowEvent>),
             Update(EventHeader, TableMapEvent, Vec<(RowEvent, RowEvent)>),
             Delete(EventHeader, TableMapEvent, Vec<RowEvent>),
+            Heartbeat(EventHeader),
+            // Filter threw, rows are (before, after) images
+            FilterFailed(
+                &'static str,
+                EventHeader,
+                TableMapEvent,
+                Vec<(Option<RowEvent>, Option<RowEvent>)>,
+                PhpException,
+            ),
         }
 
         let mut events_to_process: Option<EventToProcess> = None;
+        let password = self.password.clone();
 
         let async_result: PhpResult<()> = with_runtime_block_on!(self, async {
-            if let Some(ref mut stream) = self.binlog_stream {
+            if let Some(ref mut reader) = self.binlog_reader {
                 loop {
+                    if self.stop_requested.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    // Committing the last transaction may have completed the GTID set bound
+                    if let Some(StopCondition::Gtid(_)) = self.stop_at {
+                        if self.gtid_set.is_none() {
+                            self.gtid_set = self.current_gtid.as_deref().and_then(GtidSet::parse);
+                        }
+                        if let (Some(ref stop_at), Some(ref gtid_set)) =
+                            (&self.stop_at, &self.gtid_set)
+                        {
+                            if stop_at.reached_at_gtids(gtid_set) {
+                                self.stop_requested.store(true, Ordering::SeqCst);
+                                break;
+                            }
+                        }
+                    }
+
+                    // Wake up regularly to let signal handlers call stop()
+                    let read_deadline = if self.dispatch_signals {
+                        let wake_up = Instant::now() + SIGNAL_DISPATCH_INTERVAL;
+                        Some(deadline.map_or(wake_up, |deadline| deadline.min(wake_up)))
+                    } else {
+                        deadline
+                    };
+
                     // Read next event from binlog stream
-                    let (header, data) = stream.read().await.map_err(|e| {
-                        PhpException::default(format!("Failed to read binlog event: {}", e).into())
-                    })?;
+                    let BinlogEvent {
+                        header,
+                        data,
+                        mariadb_gtid,
+                    } = match reader.read(read_deadline).await {
+                        Ok(Some(event)) => event,
+                        Err(e) => {
+                            self.connection_lost = true;
+                            return Err(PhpException::default(
+                                redact_secret(
+                                    &password,
+                                    format!("Failed to read binlog event: {}", e),
+                                )
+                                .into(),
+                            ));
+                        }
+                        Ok(None) => {
+                            self.stats.record_idle();
+                            if self.dispatch_signals {
+                                dispatch_pending_signals()?;
+                            }
+                            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                                break;
+                            }
+                            continue;
+                        }
+                    };
+
+                    self.stats.events_read += 1;
+                    self.stats.bytes_read += header.event_length as u64;
+                    self.stats.record_read(header.timestamp);
+
+                    // Transactions are sent whole, a heartbeat also ends one of a DDL statement,
+                    // which has no commit event
+                    if let EventData::HeartBeat { .. } = data {
+                        commit_transaction_gtid!(self);
+                    }
+
+                    if let Some(ref stop_at) = self.stop_at {
+                        let file = self.current_binlog_file.as_deref().unwrap_or_default();
+                        let end_position = header.next_event_position as u64;
+
+                        // Past the bound, iteration ends without the event
+                        let past = match data {
+                            EventData::HeartBeat { .. } => stop_at.reached_at_heartbeat(
+                                file,
+                                end_position,
+                                SystemTime::now()
+                                    .duration_since(UNIX_EPOCH)
+                                    .map(|d| d.as_secs() as i64)
+                                    .unwrap_or(0),
+                            ),
+                            EventData::WriteRows(_)
+                            | EventData::UpdateRows(_)
+                            | EventData::DeleteRows(_) => {
+                                stop_at.reached_at_time(header.timestamp as i64)
+                            }
+                            _ => false,
+                        };
+                        if past {
+                            self.stop_requested.store(true, Ordering::SeqCst);
+                            break;
+                        }
+
+                        // Reaching the bound, iteration ends after the event. The artificial
+                        // rotate event at the start of a file has no timestamp nor position.
+                        if header.timestamp != 0 && stop_at.reached_at_position(file, end_position)
+                        {
+                            self.stop_requested.store(true, Ordering::SeqCst);
+                        }
+                    }
+
+                    // Where reading continues on the next start without a checkpoint. The artificial
+                    // rotate event at the start of a file has no position.
+                    if header.next_event_position != 0 {
+                        self.current_binlog_position = Some(header.next_event_position as u64);
+                    }
+
+                    // Starting from a timestamp, skip rows events until the first one at or after it
+                    if let Some(start) = self.skip_before {
+                        if matches!(
+                            data,
+                            EventData::WriteRows(_)
+                                | EventData::UpdateRows(_)
+                                | EventData::DeleteRows(_)
+                        ) {
+                            if (header.timestamp as i64) < start {
+                                continue;
+                            }
+                            self.skip_before = None;
+                        }
+                    }
+
+                    // MariaDB GTID events start transactions, the binlog client doesn't parse them
+                    if let Some(gtid) = mariadb_gtid {
+                        // Transactions of DDL statements end without a commit event
+                        commit_transaction_gtid!(self);
+                        if self.use_gtid_checkpoints {
+                            let gtid_position = match self.mariadb_gtid_position.take() {
+                                Some(gtid_position) => gtid_position,
+                                // Started at a file and position, look up the GTID position once
+                                None => {
+                                    let pool = self.pool.clone().ok_or_else(|| {
+                                        PhpException::default("Stream not connected".into())
+                                    })?;
+                                    mariadb_gtid::binlog_gtid_pos(
+                                        &pool,
+                                        self.current_binlog_file.as_deref().unwrap_or_default(),
+                                        (header.next_event_position as u64)
+                                            .saturating_sub(header.event_length as u64),
+                                    )
+                                    .await
+                                    .map_err(|e| {
+                                        PhpException::default(
+                                            format!("Failed to track MariaDB GTID: {}", e).into(),
+                                        )
+                                    })?
+                                }
+                            };
+                            self.mariadb_gtid_position = Some(gtid_position);
+                            self.uncommitted_gtid = Some(gtid.to_string());
+                        }
+                        self.loop_prevention.start_transaction(&gtid.to_string());
+                        self.transaction_gtid = Some(gtid.to_string());
+                        self.rows_query = None;
+                        continue;
+                    }
+
+                    // Loop prevention drops rows events before filters or event objects see them
+                    let rows_table_id = match data {
+                        EventData::WriteRows(ref rows_event) => Some(rows_event.table_id),
+                        EventData::UpdateRows(ref rows_event) => Some(rows_event.table_id),
+                        EventData::DeleteRows(ref rows_event) => Some(rows_event.table_id),
+                        _ => None,
+                    };
+                    if let (Some(table_id), true) =
+                        (rows_table_id, self.loop_prevention.is_enabled())
+                    {
+                        let (schema, table) = self
+                            .table_map
+                            .get(&table_id)
+                            .map(|table_map| {
+                                (
+                                    table_map.database_name.as_str(),
+                                    table_map.table_name.as_str(),
+                                )
+                            })
+                            .unwrap_or_default();
+                        if self
+                            .loop_prevention
+                            .ignores_rows(header.server_id, schema, table)
+                        {
+                            self.stats.events_filtered += 1;
+                            continue;
+                        }
+                    }
 
                     match data {
+                        // Track the binlog file so checkpoints, positions and restarts follow rotations
+                        EventData::Rotate(rotate_event) => {
+                            self.current_binlog_file = Some(rotate_event.binlog_filename.clone());
+                            self.current_binlog_position = Some(rotate_event.binlog_position);
+                            continue;
+                        }
+
+                        // Remember GTID of the transaction the following row events belong to
+                        EventData::Gtid(gtid_event) => {
+                            // Transactions of DDL statements end without a commit event
+                            commit_transaction_gtid!(self);
+                            let gtid = gtid_event.gtid.to_string();
+                            if self.use_gtid_checkpoints && !self.is_mariadb {
+                                self.uncommitted_gtid = Some(gtid.clone());
+                            }
+                            self.loop_prevention.start_transaction(&gtid);
+                            self.transaction_gtid = Some(gtid);
+                            self.rows_query = None;
+                            continue;
+                        }
+
+                        // Transaction ends, row based transactions commit with a XID event
+                        // or a COMMIT query for non-transactional tables
+                        EventData::Xid(_) => {
+                            commit_transaction_gtid!(self);
+                            self.loop_prevention.end_transaction();
+                            self.rows_query = None;
+                            continue;
+                        }
+                        EventData::Query(query_event) if query_event.query == "COMMIT" => {
+                            commit_transaction_gtid!(self);
+                            self.loop_prevention.end_transaction();
+                            self.rows_query = None;
+                            continue;
+                        }
+
+                        // Statement text of the following rows events (binlog_rows_query_log_events)
+                        EventData::RowsQuery(rows_query_event) => {
+                            self.loop_prevention
+                                .start_statement(&rows_query_event.query);
+                            self.rows_query = Some(rows_query_event.query);
+                            continue;
+                        }
+
+                        // The server sends heartbeats while there are no events to send
+                        EventData::HeartBeat { .. } => {
+                            self.stats.record_heartbeat();
+                            if self.options.heartbeat_events {
+                                events_to_process = Some(EventToProcess::Heartbeat(header));
+                                break;
+                            }
+                            continue;
+                        }
+
                         // Handle table map events to maintain column metadata
                         EventData::TableMap(table_map_event) => {
                             self.table_map
@@ -481,15 +1362,26 @@ impl MySQLStreamDriver {
                                     ) {
                                         Ok(false) => {
                                             // Event is filtered out, skip it and continue to next event
+                                            self.stats.events_filtered += 1;
                                             continue;
                                         }
                                         Ok(true) => {
                                             // Event is accepted, continue processing
                                         }
                                         Err(e) => {
-                                            // Filter error - log and skip this event
-                                            eprintln!("Filter error: {:?}", e);
-                                            continue;
+                                            // Filter error - handled by the error policy
+                                            events_to_process = Some(EventToProcess::FilterFailed(
+                                                "INSERT",
+                                                header,
+                                                table_map.clone(),
+                                                write_rows_event
+                                                    .rows
+                                                    .into_iter()
+                                                    .map(|row| (None, Some(row)))
+                                                    .collect(),
+                                                e,
+                                            ));
+                                            break;
                                         }
                                     }
                                 }
@@ -518,15 +1410,28 @@ impl MySQLStreamDriver {
                                     ) {
                                         Ok(false) => {
                                             // Event is filtered out, skip it and continue to next event
+                                            self.stats.events_filtered += 1;
                                             continue;
                                         }
                                         Ok(true) => {
                                             // Event is accepted, continue processing
                                         }
                                         Err(e) => {
-                                            // Filter error - log and skip this event
-                                            eprintln!("Filter error: {:?}", e);
-                                            continue;
+                                            // Filter error - handled by the error policy
+                                            events_to_process = Some(EventToProcess::FilterFailed(
+                                                "UPDATE",
+                                                header,
+                                                table_map.clone(),
+                                                update_rows_event
+                                                    .rows
+                                                    .into_iter()
+                                                    .map(|(before, after)| {
+                                                        (Some(before), Some(after))
+                                                    })
+                                                    .collect(),
+                                                e,
+                                            ));
+                                            break;
                                         }
                                     }
                                 }
@@ -555,15 +1460,26 @@ impl MySQLStreamDriver {
                                     ) {
                                         Ok(false) => {
                                             // Event is filtered out, skip it and continue to next event
+                                            self.stats.events_filtered += 1;
                                             continue;
                                         }
                                         Ok(true) => {
                                             // Event is accepted, continue processing
                                         }
                                         Err(e) => {
-                                            // Filter error - log and skip this event
-                                            eprintln!("Filter error: {:?}", e);
-                                            continue;
+                                            // Filter error - handled by the error policy
+                                            events_to_process = Some(EventToProcess::FilterFailed(
+                                                "DELETE",
+                                                header,
+                                                table_map.clone(),
+                                                delete_rows_event
+                                                    .rows
+                                                    .into_iter()
+                                                    .map(|row| (Some(row), None))
+                                                    .collect(),
+                                                e,
+                                            ));
+                                            break;
                                         }
                                     }
                                 }
@@ -595,131 +1511,200 @@ impl MySQLStreamDriver {
         async_result?;
 
         // Process events outside the async block to avoid borrow checker issues
-        if let Some(events) = events_to_process {
-            match events {
-                EventToProcess::Insert(header, table_map, rows) => {
-                    for (_idx, row) in rows.iter().enumerate() {
-                        match self.create_insert_event_from_binlog(&header, &table_map, row) {
-                            Ok(event_obj) => {
-                                self.event_queue.push_back(event_obj);
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to create event: {:?}", e);
-                            }
-                        }
-                    }
-                    if !self.event_queue.is_empty() {
-                        self.save_current_checkpoint(&header)?;
+        let Some(events) = events_to_process else {
+            return Ok(false);
+        };
+
+        let header = match events {
+            EventToProcess::Insert(header, table_map, rows) => {
+                let source = self.event_source(&header);
+                for (idx, row) in rows.iter().enumerate() {
+                    match self.create_insert_event_from_binlog(&source, idx, &table_map, row) {
+                        Ok(event_obj) => self.event_queue.push_back(event_obj),
+                        Err(e) => self.reject_row(
+                            "conversion",
+                            "INSERT",
+                            &source,
+                            idx,
+                            &table_map,
+                            None,
+                            Some(row),
+                            e,
+                        )?,
                     }
                 }
-                EventToProcess::Update(header, table_map, rows) => {
-                    for (_idx, (before_row, after_row)) in rows.iter().enumerate() {
-                        match self.create_update_event_from_binlog(
-                            &header, &table_map, before_row, after_row,
-                        ) {
-                            Ok(event_obj) => {
-                                self.event_queue.push_back(event_obj);
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to create event: {:?}", e);
-                            }
-                        }
-                    }
-                    if !self.event_queue.is_empty() {
-                        self.save_current_checkpoint(&header)?;
+                header
+            }
+            EventToProcess::Update(header, table_map, rows) => {
+                let source = self.event_source(&header);
+                for (idx, (before_row, after_row)) in rows.iter().enumerate() {
+                    match self.create_update_event_from_binlog(
+                        &source, idx, &table_map, before_row, after_row,
+                    ) {
+                        Ok(event_obj) => self.event_queue.push_back(event_obj),
+                        Err(e) => self.reject_row(
+                            "conversion",
+                            "UPDATE",
+                            &source,
+                            idx,
+                            &table_map,
+                            Some(before_row),
+                            Some(after_row),
+                            e,
+                        )?,
                     }
                 }
-                EventToProcess::Delete(header, table_map, rows) => {
-                    for (_idx, row) in rows.iter().enumerate() {
-                        match self.create_delete_event_from_binlog(&header, &table_map, row) {
-                            Ok(event_obj) => {
-                                self.event_queue.push_back(event_obj);
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to create event: {:?}", e);
-                            }
-                        }
-                    }
-                    if !self.event_queue.is_empty() {
-                        self.save_current_checkpoint(&header)?;
+                header
+            }
+            EventToProcess::Delete(header, table_map, rows) => {
+                let source = self.event_source(&header);
+                for (idx, row) in rows.iter().enumerate() {
+                    match self.create_delete_event_from_binlog(&source, idx, &table_map, row) {
+                        Ok(event_obj) => self.event_queue.push_back(event_obj),
+                        Err(e) => self.reject_row(
+                            "conversion",
+                            "DELETE",
+                            &source,
+                            idx,
+                            &table_map,
+                            Some(row),
+                            None,
+                            e,
+                        )?,
                     }
                 }
+                header
             }
-        }
+            EventToProcess::FilterFailed(event_type, header, table_map, rows, error) => {
+                let source = self.event_source(&header);
+                let error = format!("{:?}", error);
+                for (idx, (before_row, after_row)) in rows.iter().enumerate() {
+                    self.reject_row(
+                        "filter",
+                        event_type,
+                        &source,
+                        idx,
+                        &table_map,
+                        before_row.as_ref(),
+                        after_row.as_ref(),
+                        PhpException::default(error.clone()),
+                    )?;
+                }
+                header
+            }
+            EventToProcess::Heartbeat(header) => {
+                let heartbeat = self.create_heartbeat_event(&header)?;
+                self.event_queue.push_back(heartbeat);
+                return Ok(false);
+            }
+        };
 
-        Ok(())
+        // Rejected rows were handled by the error policy, don't read them again on resume
+        self.save_current_checkpoint(&header)?;
+
+        Ok(self.event_queue.is_empty())
+    }
+
+    /// Apply the error policy to a row that cannot be delivered
+    ///
+    /// Rows of the failed event that were already queued are dropped when the policy
+    /// throws, so that the whole event is read again after a restart.
+    #[allow(clippy::too_many_arguments)]
+    fn reject_row(
+        &mut self,
+        reason: &str,
+        event_type: &str,
+        source: &EventSource,
+        ordinal: usize,
+        table_map: &TableMapEvent,
+        before: Option<&RowEvent>,
+        after: Option<&RowEvent>,
+        error: PhpException,
+    ) -> PhpResult<()> {
+        let letter = DeadLetter {
+            reason,
+            error: format!("{:?}", error),
+            r#type: event_type,
+            schema: &table_map.database_name,
+            table: &table_map.table_name,
+            checkpoint: &source.checkpoint,
+            ordinal,
+            before: before.map(|row| raw_columns(table_map, row)),
+            after: after.map(|row| raw_columns(table_map, row)),
+        };
+
+        self.stats.rows_failed += 1;
+        let result = self.error_policy.handle(&self.logger, letter);
+        if result.is_err() {
+            self.event_queue.clear();
+        }
+        result
     }
 
     fn create_insert_event_from_binlog(
         &self,
-        header: &EventHeader,
+        source: &EventSource,
+        ordinal: usize,
         table_map: &TableMapEvent,
         row: &RowEvent,
     ) -> PhpResult<Zval> {
-        let timestamp = header.timestamp as i64;
-        let checkpoint = self.generate_checkpoint(header);
-
         let after_data = self.create_data_object_from_row(table_map, row)?;
 
         self.create_event(
             "DataAccessKit\\Replication\\InsertEvent",
             "INSERT",
-            timestamp as i32,
-            &checkpoint,
-            &table_map.database_name,
-            &table_map.table_name,
+            source,
+            ordinal,
+            table_map,
             None,
             Some(after_data),
+            column_types(table_map, &[row])?,
         )
         .map(|opt| opt.unwrap())
     }
 
     fn create_update_event_from_binlog(
         &self,
-        header: &EventHeader,
+        source: &EventSource,
+        ordinal: usize,
         table_map: &TableMapEvent,
         before_row: &RowEvent,
         after_row: &RowEvent,
     ) -> PhpResult<Zval> {
-        let timestamp = header.timestamp as i64;
-        let checkpoint = self.generate_checkpoint(header);
-
         let before_data = self.create_data_object_from_row(table_map, before_row)?;
         let after_data = self.create_data_object_from_row(table_map, after_row)?;
 
         self.create_event(
             "DataAccessKit\\Replication\\UpdateEvent",
             "UPDATE",
-            timestamp as i32,
-            &checkpoint,
-            &table_map.database_name,
-            &table_map.table_name,
+            source,
+            ordinal,
+            table_map,
             Some(before_data),
             Some(after_data),
+            column_types(table_map, &[before_row, after_row])?,
         )
         .map(|opt| opt.unwrap())
     }
 
     fn create_delete_event_from_binlog(
         &self,
-        header: &EventHeader,
+        source: &EventSource,
+        ordinal: usize,
         table_map: &TableMapEvent,
         row: &RowEvent,
     ) -> PhpResult<Zval> {
-        let timestamp = header.timestamp as i64;
-        let checkpoint = self.generate_checkpoint(header);
-
         let before_data = self.create_data_object_from_row(table_map, row)?;
 
         self.create_event(
             "DataAccessKit\\Replication\\DeleteEvent",
             "DELETE",
-            timestamp as i32,
-            &checkpoint,
-            &table_map.database_name,
-            &table_map.table_name,
+            source,
+            ordinal,
+            table_map,
             Some(before_data),
             None,
+            column_types(table_map, &[row])?,
         )
         .map(|opt| opt.unwrap())
     }
@@ -1031,16 +2016,40 @@ impl MySQLStreamDriver {
         Ok(())
     }
 
+    fn create_heartbeat_event(&self, header: &EventHeader) -> PhpResult<Zval> {
+        let ce = zend::ClassEntry::try_find("DataAccessKit\\Replication\\HeartbeatEvent")
+            .ok_or_else(|| PhpException::default("Class HeartbeatEvent not found".into()))?;
+        let obj = ext_php_rs::types::ZendObject::new(ce);
+
+        // Heartbeats carry no timestamp, use the time of arrival
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let server_id = header.server_id as i64;
+        let binlog_file = self.current_binlog_file.clone().unwrap_or_default();
+        let binlog_position = header.next_event_position as i64;
+
+        obj.try_call_method(
+            "__construct",
+            vec![&timestamp, &server_id, &binlog_file, &binlog_position],
+        )?;
+
+        let mut event_zval = Zval::new();
+        event_zval.set_object(&mut *obj.into_raw());
+        Ok(event_zval)
+    }
+
     fn create_event(
         &self,
         class_name: &str,
         event_type: &str,
-        timestamp: i32,
-        checkpoint: &str,
-        schema: &str,
-        table: &str,
+        source: &EventSource,
+        ordinal: usize,
+        table_map: &TableMapEvent,
         before_data: Option<Zval>,
         after_data: Option<Zval>,
+        column_types: Zval,
     ) -> PhpResult<Option<Zval>> {
         // Find the event class
         let ce = zend::ClassEntry::try_find(class_name).ok_or_else(|| {
@@ -1051,9 +2060,15 @@ impl MySQLStreamDriver {
         let obj = ext_php_rs::types::ZendObject::new(ce);
 
         // Prepare constructor parameters
-        let timestamp_i64 = timestamp as i64;
+        let checkpoint = source.checkpoint.as_str();
+        let server_id = source.server_id as i64;
+        let binlog_file = source.binlog_file.as_str();
+        let binlog_position = source.binlog_position as i64;
+        let ordinal = ordinal as i64;
+        let schema = table_map.database_name.as_str();
+        let table = table_map.table_name.as_str();
         let mut params: Vec<&dyn ext_php_rs::convert::IntoZvalDyn> =
-            vec![&event_type, &timestamp_i64, &checkpoint, &schema, &table];
+            vec![&event_type, &source.timestamp, &checkpoint, &schema, &table];
 
         // Add objects to params in the correct order
         if let Some(ref before) = before_data {
@@ -1063,6 +2078,15 @@ impl MySQLStreamDriver {
             params.push(after);
         }
 
+        // Origin and position of the event follow the row data
+        params.push(&server_id);
+        params.push(&binlog_file);
+        params.push(&binlog_position);
+        params.push(&source.gtid);
+        params.push(&ordinal);
+        params.push(&source.query);
+        params.push(&column_types);
+
         // Call constructor
         let _result = obj.try_call_method("__construct", params)?;
 
@@ -1073,61 +2097,371 @@ impl MySQLStreamDriver {
     }
 }
 
-impl StreamDriver for MySQLStreamDriver {
-    fn connect(&mut self) -> PhpResult<()> {
-        if self.connected {
-            return Ok(());
+/// Run PHP signal handlers installed by pcntl_signal() while blocked reading the binlog
+fn dispatch_pending_signals() -> PhpResult<()> {
+    if let Ok(dispatch) = ZendCallable::try_from_name("pcntl_signal_dispatch") {
+        dispatch.try_call(vec![])?;
+    }
+    Ok(())
+}
+
+/// Whether signal handlers have to be dispatched by the stream, PHP runs them itself with
+/// pcntl_async_signals() turned on, once the read returns
+fn needs_signal_dispatch() -> bool {
+    if ZendCallable::try_from_name("pcntl_signal_dispatch").is_err() {
+        return false;
+    }
+    !ZendCallable::try_from_name("pcntl_async_signals")
+        .and_then(|async_signals| async_signals.try_call(vec![]))
+        .is_ok_and(|enabled| enabled.bool() == Some(true))
+}
+
+/// Whether connecting failed to negotiate TLS, the only failure PREFERRED retries without it
+fn is_tls_error(error: &mysql_async::Error) -> bool {
+    match error {
+        mysql_async::Error::Driver(DriverError::NoClientSslFlagFromServer) => true,
+        mysql_async::Error::Io(IoError::Tls(_)) => true,
+        // The TLS library reports handshake failures as invalid data on the socket
+        mysql_async::Error::Io(IoError::Io(e)) => e.kind() == std::io::ErrorKind::InvalidData,
+        _ => false,
+    }
+}
+
+/// Whether a statement failed for missing privileges (ER_SPECIFIC_ACCESS_DENIED_ERROR)
+fn is_access_denied(error: &mysql_async::Error) -> bool {
+    matches!(error, mysql_async::Error::Server(e) if e.code == 1227)
+}
+
+/// Run a connection attempt, failing when it does not finish within the timeout
+async fn with_connect_timeout<T>(
+    timeout: Option<Duration>,
+    connect: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connect)
+            .await
+            .unwrap_or_else(|_| Err(format!("connection timed out after {:?}", timeout))),
+        None => connect.await,
+    }
+}
+
+/// Replace a secret, raw or as the password of a connection URL, in a message
+fn redact_secret(secret: &str, message: String) -> String {
+    if secret.is_empty() {
+        return message;
+    }
+
+    // Encoded the way URLs printed in error messages carry it
+    let encoded = Url::parse("mysql://user@localhost")
+        .ok()
+        .and_then(|mut url| {
+            url.set_password(Some(secret)).ok()?;
+            url.password().map(str::to_string)
+        });
+
+    let message = message.replace(secret, "***");
+    match encoded {
+        Some(encoded) if encoded != secret => message.replace(&encoded, "***"),
+        _ => message,
+    }
+}
+
+/// Column name (or index without metadata) to the binlog value as received
+fn raw_columns(table_map: &TableMapEvent, row: &RowEvent) -> Vec<(String, serde_json::Value)> {
+    row.column_values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let name = table_map
+                .table_metadata
+                .as_ref()
+                .and_then(|metadata| metadata.columns.get(i))
+                .and_then(|column| column.column_name.clone())
+                .unwrap_or_else(|| i.to_string());
+            (name, raw_value(value))
+        })
+        .collect()
+}
+
+/// Binlog value as received, in the codec's JSON form with binary strings in base64
+///
+/// Numbers stay numbers, SET and ENUM their bitmask and index, TIMESTAMP microseconds.
+fn raw_value(value: &ColumnValue) -> serde_json::Value {
+    match value {
+        ColumnValue::None => serde_json::Value::Null,
+        ColumnValue::Tiny(i) => serde_json::Value::from(*i),
+        ColumnValue::Short(i) => serde_json::Value::from(*i),
+        ColumnValue::Long(i) => serde_json::Value::from(*i),
+        ColumnValue::LongLong(i) => serde_json::Value::from(*i),
+        ColumnValue::Year(y) => serde_json::Value::from(*y),
+        ColumnValue::Bit(value) => serde_json::Value::from(*value),
+        ColumnValue::Set(value) => serde_json::Value::from(*value),
+        ColumnValue::Enum(value) => serde_json::Value::from(*value),
+        ColumnValue::Timestamp(ts) => serde_json::Value::from(*ts),
+        ColumnValue::Float(f) => raw_float(*f as f64),
+        ColumnValue::Double(d) => raw_float(*d),
+        ColumnValue::Decimal(text)
+        | ColumnValue::Date(text)
+        | ColumnValue::DateTime(text)
+        | ColumnValue::Time(text) => serde_json::Value::from(text.as_str()),
+        ColumnValue::String(bytes) | ColumnValue::Blob(bytes) | ColumnValue::Json(bytes) => {
+            codec::encode_bytes(bytes)
         }
+    }
+}
 
-        with_runtime_block_on!(self, async {
-            // Build MySQL connection options
-            // For replication, we don't connect to a specific database
-            // The replication user needs REPLICATION SLAVE/CLIENT privileges, not database access
-            let opts = OptsBuilder::default()
-                .ip_or_hostname(&self.host)
-                .tcp_port(self.port)
-                .user(Some(&self.user))
-                .pass(Some(&self.password));
+/// JSON has no NaN or infinity, those are kept as their text
+fn raw_float(f: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(f).map_or_else(
+        || serde_json::Value::from(f.to_string()),
+        serde_json::Value::Number,
+    )
+}
 
-            // Create connection pool
-            let pool = Pool::new(opts);
+/// Column name to the binlog type of its values, e.g. `long`, `string`, `json` or `set`
+///
+/// SET and ENUM columns are known from the table metadata, other columns from the values
+/// of the rows, a column that is null in all of them is left out.
+fn column_types(table_map: &TableMapEvent, rows: &[&RowEvent]) -> PhpResult<Zval> {
+    let mut types = ZendHashTable::new();
+    let columns = table_map
+        .table_metadata
+        .as_ref()
+        .map_or(&[][..], |metadata| metadata.columns.as_slice());
+
+    for (i, column) in columns.iter().enumerate() {
+        let Some(ref name) = column.column_name else {
+            continue;
+        };
+        let column_type = if column.set_string_values.is_some() {
+            Some("set")
+        } else if column.enum_string_values.is_some() {
+            Some("enum")
+        } else {
+            rows.iter()
+                .filter_map(|row| row.column_values.get(i))
+                .find_map(value_type)
+        };
+        if let Some(column_type) = column_type {
+            types.insert(name.as_str(), column_type)?;
+        }
+    }
 
-            // Validate MySQL configuration (this also sets is_mariadb and use_gtid_checkpoints)
-            self.validate_mysql_config(&pool).await.map_err(|e| {
-                PhpException::default(format!("MySQL configuration invalid: {}", e).into())
-            })?;
+    let mut zval = Zval::new();
+    zval.set_hashtable(types);
+    Ok(zval)
+}
 
-            // Get current GTID position for binlog streaming (only for MySQL with GTID)
-            // Only set if not already set by checkpoint
-            if self.use_gtid_checkpoints && !self.is_mariadb && self.current_gtid.is_none() {
-                let current_gtid = self.get_current_gtid(&pool).await.map_err(|e| {
-                    PhpException::default(format!("Failed to get GTID: {}", e).into())
+/// Binlog type of a value, None for null
+fn value_type(value: &ColumnValue) -> Option<&'static str> {
+    Some(match value {
+        ColumnValue::None => return None,
+        ColumnValue::Tiny(_) => "tiny",
+        ColumnValue::Short(_) => "short",
+        ColumnValue::Long(_) => "long",
+        ColumnValue::LongLong(_) => "longlong",
+        ColumnValue::Float(_) => "float",
+        ColumnValue::Double(_) => "double",
+        ColumnValue::Decimal(_) => "decimal",
+        ColumnValue::Date(_) => "date",
+        ColumnValue::DateTime(_) => "datetime",
+        ColumnValue::Time(_) => "time",
+        ColumnValue::Timestamp(_) => "timestamp",
+        ColumnValue::Year(_) => "year",
+        ColumnValue::String(_) => "string",
+        ColumnValue::Blob(_) => "blob",
+        ColumnValue::Json(_) => "json",
+        ColumnValue::Bit(_) => "bit",
+        ColumnValue::Set(_) => "set",
+        ColumnValue::Enum(_) => "enum",
+    })
+}
+
+/// Read the checkpoint property of an event object
+fn checkpoint_of(event: &Zval) -> Option<String> {
+    event
+        .object()
+        .and_then(|obj| obj.get_property::<String>("checkpoint").ok())
+}
+
+impl MySQLStreamDriver {
+    /// Connect and open the binlog stream at the checkpoint or current position
+    fn start(&mut self) -> PhpResult<()> {
+        // Establish connection if not connected (as per spec)
+        if !self.connected {
+            self.connect()?;
+        }
+
+        // Clear the event queue on start
+        self.event_queue.clear();
+        self.current_event = None;
+
+        // Load checkpoint BEFORE creating BinlogClient so it uses the checkpoint position
+        // instead of the current database position
+        self.skip_before = None;
+        if self.is_mariadb {
+            // MariaDB GTID positions are tracked while reading, not taken from connect()
+            self.current_gtid = None;
+            self.mariadb_gtid_position = None;
+        }
+        let checkpoint = match self.load_checkpoint_if_available()? {
+            Some(checkpoint) => Some(checkpoint),
+            None => match self.options.start.clone() {
+                StartPosition::Checkpoint(checkpoint) => {
+                    self.apply_checkpoint(&checkpoint)?;
+                    Some(checkpoint)
+                }
+                _ => None,
+            },
+        };
+
+        let start = match checkpoint {
+            Some(ref checkpoint) => self.validate_checkpoint(checkpoint)?,
+            // connect() already positioned the stream at the current end of the binlog
+            None if self.options.start == StartPosition::Latest => None,
+            None => Some(self.options.start.clone()),
+        };
+        if let Some(start) = start {
+            with_runtime_block_on!(self, async { self.apply_start_position(start).await })
+                .map_err(|e| {
+                    PhpException::default(format!("Failed to apply start position: {}", e).into())
                 })?;
-                self.current_gtid = Some(current_gtid);
+        }
+
+        // Positioning may have replaced current_gtid, parse it again at the first transaction
+        self.gtid_set = None;
+        self.uncommitted_gtid = None;
+
+        self.resolve_stop_condition()?;
+
+        // Initialize binlog client with checkpoint position (async)
+        with_runtime_block_on!(self, async { self.initialize_binlog_client().await })?;
+
+        self.position = 0;
+        self.event_iterator_started = true;
+        self.idle = false;
+        self.dispatch_signals = needs_signal_dispatch();
+
+        Ok(())
+    }
+
+    /// Move to the next event, from the queue or the binlog, waiting at most idle_timeout
+    fn advance(&mut self) -> PhpResult<()> {
+        let stopping = self.stop_requested.load(Ordering::SeqCst);
+
+        if self.event_queue.is_empty() && !stopping {
+            let deadline = self
+                .options
+                .idle_timeout
+                .map(|idle_timeout| Instant::now() + idle_timeout);
+            self.read_next_event(deadline)?;
+        }
+
+        let delivered = self.event_queue.pop_front();
+        if let Some(ref event) = delivered {
+            self.track_delivered(event);
+        }
+        self.current_event = delivered;
+
+        if self.current_event.is_some() {
+            self.idle = false;
+        } else if self.stop_requested.load(Ordering::SeqCst) {
+            self.idle = false;
+            self.finish_stop()?;
+        } else {
+            self.idle = true;
+        }
+
+        Ok(())
+    }
+
+    /// Remember checkpoint and stats of an event handed to PHP
+    fn track_delivered(&mut self, event: &Zval) {
+        // Heartbeat events have no checkpoint and don't count as emitted
+        if let Some(checkpoint) = checkpoint_of(event) {
+            self.last_checkpoint = Some(checkpoint);
+
+            self.emitted += 1;
+            if self
+                .options
+                .max_events
+                .is_some_and(|max_events| self.emitted >= max_events)
+            {
+                // Rows left over from the last rows event are past the bound as well
+                self.stop_requested.store(true, Ordering::SeqCst);
+                self.event_queue.clear();
             }
 
-            // Always get binlog file/position for checkpointing if not set by checkpoint
-            if self.current_binlog_file.is_none() || self.current_binlog_position.is_none() {
-                let (binlog_file, binlog_position) =
-                    self.get_current_binlog_position(&pool).await.map_err(|e| {
-                        PhpException::default(
-                            format!("Failed to get binlog position: {}", e).into(),
-                        )
-                    })?;
+            if let Some(obj) = event.object() {
+                let property = |name: &str| obj.get_property::<String>(name).unwrap_or_default();
+                self.stats.record_emitted(
+                    &property("type"),
+                    &property("schema"),
+                    &property("table"),
+                );
+            }
+        }
+    }
 
-                // Store for checkpoint generation only if not already set
-                if self.current_binlog_file.is_none() {
-                    self.current_binlog_file = Some(binlog_file);
+    /// Close the binlog connection and save the checkpoint of the last event handed to PHP
+    ///
+    /// Rows are queued only after their checkpoint is saved, so stopping lets the queue
+    /// drain first - dropping queued rows would skip them on resume.
+    fn finish_stop(&mut self) -> PhpResult<()> {
+        // Dropping the reader cancels a pending read and stops the prefetch thread
+        self.binlog_reader = None;
+        self.binlog_opts = None;
+
+        if let (Some(ref checkpointer), Some(ref checkpoint)) =
+            (&self.checkpointer, &self.last_checkpoint)
+        {
+            checkpointer.save_checkpoint(checkpoint)?;
+            self.stats.checkpoint_saves += 1;
+        }
+
+        self.logger.info(
+            "Stream stopped",
+            &[("checkpoint", self.last_checkpoint.as_deref().unwrap_or(""))],
+        );
+
+        Ok(())
+    }
+}
+
+impl StreamDriver for MySQLStreamDriver {
+    fn connect(&mut self) -> PhpResult<()> {
+        if self.connected {
+            return Ok(());
+        }
+
+        if let Some(ref provider) = self.credentials {
+            let credentials = provider.credentials()?;
+            if let Some(user) = credentials.user {
+                self.user = user;
+            }
+            self.password = credentials.password;
+        }
+
+        // Hosts are tried in order, the first reachable one with a valid configuration wins
+        let mut errors = Vec::new();
+        for index in 0..self.hosts.len() {
+            match self.connect_host(index)? {
+                Ok(()) => return Ok(()),
+                Err(e) if self.hosts.len() == 1 => {
+                    return Err(PhpException::default(e.into()));
                 }
-                if self.current_binlog_position.is_none() {
-                    self.current_binlog_position = Some(binlog_position);
+                Err(e) => {
+                    let host = format!("{}:{}", self.host, self.port);
+                    self.logger
+                        .warning("Host not available", &[("host", &host), ("error", &e)]);
+                    errors.push(format!("{}: {}", host, e));
                 }
             }
-            self.pool = Some(pool);
-            self.connected = true;
+        }
 
-            Ok(())
-        })
+        Err(PhpException::default(
+            format!("Failed to connect to any host: {}", errors.join("; ")).into(),
+        ))
     }
 
     fn disconnect(&mut self) -> PhpResult<()> {
@@ -1136,9 +2470,14 @@ impl StreamDriver for MySQLStreamDriver {
         }
 
         self.pool = None;
-        self.binlog_client = None;
-        self.binlog_stream = None;
+        self.binlog_opts = None;
+        self.binlog_reader = None;
         self.current_gtid = None;
+        self.transaction_gtid = None;
+        self.uncommitted_gtid = None;
+        self.rows_query = None;
+        self.gtid_set = None;
+        self.mariadb_gtid_position = None;
         self.current_binlog_file = None;
         self.current_binlog_position = None;
         self.is_mariadb = false;
@@ -1146,6 +2485,9 @@ impl StreamDriver for MySQLStreamDriver {
         self.current_event = None;
         self.event_queue.clear();
         self.event_iterator_started = false;
+        self.idle = false;
+        self.stop_requested.store(false, Ordering::SeqCst);
+        self.last_checkpoint = None;
         self.connected = false;
         self.table_map.clear();
         self.checkpointer = None;
@@ -1166,6 +2508,11 @@ impl StreamDriver for MySQLStreamDriver {
         Ok(())
     }
 
+    fn set_logger(&mut self, logger: &Zval) -> PhpResult<()> {
+        self.logger = Logger::new(logger)?;
+        Ok(())
+    }
+
     fn set_filter(&mut self, filter: &Zval) -> PhpResult<()> {
         let wrapper = if filter.is_null() {
             None
@@ -1206,56 +2553,84 @@ impl StreamDriver for MySQLStreamDriver {
             return Err(PhpException::default("Stream not connected or not started".into()).into());
         }
 
-        self.position += 1;
+        // After an idle timeout there is no current event to move past
+        if self.current_event.is_some() {
+            self.position += 1;
+        }
+
+        self.advance()
+    }
 
-        // First check if we have events in the queue
-        if let Some(event) = self.event_queue.pop_front() {
-            self.current_event = Some(event);
-        } else {
-            // Queue is empty, fetch more events from binlog
-            self.fetch_next_event()?;
+    fn fetch_batch(&mut self, max_events: usize, max_wait: Duration) -> PhpResult<Vec<Zval>> {
+        if !self.event_iterator_started {
+            self.start()?;
+        }
 
-            // After fetching, pop the first event from queue
+        let deadline = Instant::now() + max_wait;
+        let mut batch = Vec::new();
+
+        while batch.len() < max_events {
+            // Drain rows already buffered from multi-row events first
             if let Some(event) = self.event_queue.pop_front() {
-                self.current_event = Some(event);
-            } else {
-                // No events available
-                self.current_event = None;
+                self.track_delivered(&event);
+                batch.push(event);
+                continue;
             }
-        }
 
-        Ok(())
-    }
+            if self.stop_requested.load(Ordering::SeqCst) {
+                if self.binlog_reader.is_some() {
+                    self.finish_stop()?;
+                }
+                break;
+            }
 
-    fn rewind(&mut self) -> PhpResult<()> {
-        // Establish connection if not connected (as per spec)
-        if !self.connected {
-            self.connect()?;
-        }
+            // Reads what has already arrived even once the deadline passed, so that
+            // a zero wait polls without blocking
+            self.read_next_event(Some(deadline))?;
 
-        // Clear the event queue on rewind
-        self.event_queue.clear();
-        self.current_event = None;
+            if self.event_queue.is_empty() {
+                // Deadline passed without another event
+                break;
+            }
+        }
 
-        // Load checkpoint BEFORE creating BinlogClient so it uses the checkpoint position
-        // instead of the current database position
-        self.load_checkpoint_if_available()?;
+        self.position += batch.len() as u64;
+        Ok(batch)
+    }
 
-        // Initialize binlog client with checkpoint position (async)
-        with_runtime_block_on!(self, async { self.initialize_binlog_client().await })?;
+    fn stats(&self) -> PhpResult<Zval> {
+        self.stats.to_array(
+            self.last_checkpoint.as_deref(),
+            self.binlog_reader
+                .as_ref()
+                .map_or(0, BinlogReader::buffered),
+        )
+    }
 
-        self.position = 0;
-        self.event_iterator_started = true;
+    fn metrics(&self, name: &str) -> PhpResult<String> {
+        let server_id = self
+            .binlog_opts
+            .as_ref()
+            .map(|opts| opts.server_id.to_string())
+            .or_else(|| self.server_id.map(|server_id| server_id.to_string()))
+            .unwrap_or_default();
 
-        // Fetch the first batch of events
-        self.fetch_next_event()?;
+        Ok(self.stats.to_openmetrics(&[
+            ("host", format!("{}:{}", self.host, self.port)),
+            ("server_id", server_id),
+            ("stream", name.to_string()),
+        ]))
+    }
 
-        // Pop the first event from queue to current_event
-        if let Some(event) = self.event_queue.pop_front() {
-            self.current_event = Some(event);
+    fn rewind(&mut self) -> PhpResult<()> {
+        // Iteration ended by idle timeout continues where it stopped
+        if self.event_iterator_started && self.idle {
+            return self.advance();
         }
 
-        Ok(())
+        self.start()?;
+
+        self.advance()
     }
 
     fn valid(&self) -> PhpResult<bool> {
@@ -0,0 +1,222 @@
+use super::gtid_set::GtidSet;
+use super::mariadb_gtid::{MariaDbGtid, MariaDbGtidPosition, MARIADB_GTID_EVENT};
+use futures_core::Stream;
+use mysql_async::binlog::events::{Event, TransactionPayloadEvent};
+use mysql_async::binlog::BinlogVersion;
+use mysql_async::prelude::Queryable;
+use mysql_async::{BinlogStream, BinlogStreamRequest, Conn, GnoInterval, OptsBuilder, Sid};
+use mysql_binlog_connector_rust::{
+    binlog_parser::BinlogParser,
+    event::{event_data::EventData, event_header::EventHeader},
+};
+use std::collections::HashMap;
+use std::future::poll_fn;
+use std::io::Cursor;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+/// Event type of MySQL's Transaction_payload event, holds a compressed transaction
+const TRANSACTION_PAYLOAD_EVENT: u8 = 40;
+
+/// Where the server starts sending the binlog
+#[derive(Debug, Clone)]
+pub enum DumpPosition {
+    /// Every transaction not in the MySQL GTID set
+    Gtid(GtidSet),
+    /// Every transaction after the MariaDB GTID position
+    MariaDbGtid(MariaDbGtidPosition),
+    /// Binlog file and position, the oldest file when empty
+    File(String, u64),
+}
+
+/// Binlog event as parsed by the binlog connector
+pub struct BinlogEvent {
+    pub header: EventHeader,
+    pub data: EventData,
+    /// GTID of a MariaDB GTID event, which the binlog connector does not parse
+    pub mariadb_gtid: Option<MariaDbGtid>,
+}
+
+/// Everything needed to open a binlog connection, cheap to send to another thread
+#[derive(Debug, Clone)]
+pub struct BinlogConnectionOpts {
+    pub opts: OptsBuilder,
+    pub server_id: u32,
+    pub position: DumpPosition,
+    pub heartbeat_interval: u64,
+    pub read_timeout: u64,
+}
+
+/// Replication connection streaming binlog events
+///
+/// mysql_async speaks the protocol, so the connection gets the same keepalive, TLS and socket
+/// options as the metadata pool; events are parsed into the binlog connector's types.
+pub struct BinlogConnection {
+    stream: BinlogStream,
+    parser: BinlogParser,
+    read_timeout: Duration,
+    last_read: Instant,
+    /// Bytes of the last Transaction_payload event still to be yielded decompressed
+    payload_left: u64,
+}
+
+impl BinlogConnection {
+    pub async fn open(opts: &BinlogConnectionOpts) -> Result<Self, String> {
+        let mut conn = Conn::new(opts.opts.clone())
+            .await
+            .map_err(|e| format!("Failed to connect: {}", e))?;
+
+        // Events carry a CRC32 footer unless the server writes the binlog without checksums
+        let checksum: Option<String> = conn
+            .query_first("SELECT @@GLOBAL.binlog_checksum")
+            .await
+            .map_err(|e| format!("Failed to query binlog_checksum: {}", e))?;
+        let checksum_length = match checksum.as_deref() {
+            Some(checksum) if checksum.eq_ignore_ascii_case("NONE") => 0,
+            _ => 4,
+        };
+
+        // Period in nanoseconds
+        conn.query_drop(format!(
+            "SET @master_heartbeat_period = {}",
+            opts.heartbeat_interval * 1_000_000_000
+        ))
+        .await
+        .map_err(|e| format!("Failed to set heartbeat period: {}", e))?;
+
+        // MariaDB sends GTID events only to replicas that announce GTID support
+        conn.query_drop("SET @mariadb_slave_capability = 4")
+            .await
+            .map_err(|e| format!("Failed to set replica capability: {}", e))?;
+
+        let request = BinlogStreamRequest::new(opts.server_id);
+        let stream = match opts.position {
+            DumpPosition::Gtid(ref gtid_set) => {
+                let sids = gtid_set
+                    .sources()
+                    .map(|(uuid, ranges)| sid(uuid, ranges))
+                    .collect::<Result<Vec<_>, _>>()?;
+                conn.get_binlog_stream(request.with_gtid().with_gtid_set(sids))
+                    .await
+            }
+            DumpPosition::MariaDbGtid(ref gtid_position) => {
+                // The server looks up where the position continues, strict mode fails
+                // instead of skipping ahead when its binlog no longer has it
+                conn.query_drop(format!("SET @slave_connect_state = '{}'", gtid_position))
+                    .await
+                    .map_err(|e| format!("Failed to set slave_connect_state: {}", e))?;
+                conn.query_drop("SET @slave_gtid_strict_mode = 1")
+                    .await
+                    .map_err(|e| format!("Failed to set slave_gtid_strict_mode: {}", e))?;
+                conn.get_binlog_stream(request.with_filename(b"").with_pos(4u64))
+                    .await
+            }
+            DumpPosition::File(ref file, position) => {
+                conn.get_binlog_stream(request.with_filename(file.as_bytes()).with_pos(position))
+                    .await
+            }
+        }
+        .map_err(|e| format!("Failed to request binlog: {}", e))?;
+
+        Ok(BinlogConnection {
+            stream,
+            parser: BinlogParser {
+                checksum_length,
+                table_map_event_by_table_id: HashMap::new(),
+            },
+            read_timeout: Duration::from_secs(opts.read_timeout),
+            last_read: Instant::now(),
+            payload_left: 0,
+        })
+    }
+
+    /// Read the next event, returns None if the deadline passed before it arrived
+    ///
+    /// Cancel-safe, the state of a partially received event is kept by the stream.
+    pub async fn read(&mut self, deadline: Option<Instant>) -> Result<Option<BinlogEvent>, String> {
+        loop {
+            // The server sends heartbeats while idle, silence for longer means the connection is dead
+            let timeout_at = self.last_read + self.read_timeout;
+            let wait_until = deadline.map_or(timeout_at, |deadline| deadline.min(timeout_at));
+
+            let next = poll_fn(|cx| Pin::new(&mut self.stream).poll_next(cx));
+            let event = match tokio::time::timeout_at(wait_until.into(), next).await {
+                Ok(Some(Ok(event))) => event,
+                Ok(Some(Err(e))) => return Err(e.to_string()),
+                Ok(None) => return Err("Binlog stream closed by the server".to_string()),
+                Err(_) if Instant::now() >= timeout_at => {
+                    return Err(format!(
+                        "No binlog data received within {} seconds",
+                        self.read_timeout.as_secs()
+                    ));
+                }
+                Err(_) => return Ok(None),
+            };
+            self.last_read = Instant::now();
+
+            // The parser reads compressed transactions from the Transaction_payload event itself,
+            // the stream yields the decompressed events again right after it, skip those
+            if self.payload_left > 0 {
+                self.payload_left = self
+                    .payload_left
+                    .saturating_sub(event.header().event_size() as u64);
+                continue;
+            }
+            if event.header().event_type_raw() == TRANSACTION_PAYLOAD_EVENT {
+                self.payload_left = event
+                    .read_event::<TransactionPayloadEvent<'_>>()
+                    .map_err(|e| format!("Failed to read transaction payload: {}", e))?
+                    .uncompressed_size();
+            }
+
+            return self.parse(&event).map(Some);
+        }
+    }
+
+    fn parse(&mut self, event: &Event) -> Result<BinlogEvent, String> {
+        // Written back as received from the server, checksum included
+        let mut bytes = Vec::new();
+        event
+            .write(BinlogVersion::Version4, &mut bytes)
+            .map_err(|e| format!("Failed to serialize binlog event: {}", e))?;
+
+        let (header, data) = self
+            .parser
+            .next(&mut Cursor::new(bytes))
+            .map_err(|e| format!("Failed to parse binlog event: {}", e))?;
+
+        let mariadb_gtid = if header.event_type == MARIADB_GTID_EVENT {
+            Some(
+                MariaDbGtid::from_event(header.server_id, event.data())
+                    .ok_or_else(|| "Truncated MariaDB GTID event".to_string())?,
+            )
+        } else {
+            None
+        };
+
+        Ok(BinlogEvent {
+            header,
+            data,
+            mariadb_gtid,
+        })
+    }
+}
+
+/// GTID source in the form of the binlog dump request
+fn sid(uuid: &str, ranges: &[(u64, u64)]) -> Result<Sid<'static>, String> {
+    let invalid = || format!("Invalid GTID source '{}'", uuid);
+
+    let hex: String = uuid.chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+
+    // Intervals of the request exclude their end
+    Ok(ranges.iter().fold(Sid::new(bytes), |sid, (start, end)| {
+        sid.with_interval(GnoInterval::new(*start, end + 1))
+    }))
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// MySQL GTID set, e.g. `3E11FA47-71CA-11E1-9E33-C80AA9429562:1-5:7,...`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GtidSet {
+    intervals: BTreeMap<String, Vec<(u64, u64)>>,
+}
+
+impl GtidSet {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut set = GtidSet::default();
+
+        for source in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let mut parts = source.split(':');
+            let uuid = parts.next()?.to_ascii_lowercase();
+            let ranges = set.intervals.entry(uuid).or_default();
+
+            for range in parts {
+                let (start, end) = match range.split_once('-') {
+                    Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+                    None => {
+                        let number = range.parse().ok()?;
+                        (number, number)
+                    }
+                };
+                ranges.push((start, end));
+            }
+            normalize(ranges);
+        }
+
+        if set.intervals.is_empty() {
+            None
+        } else {
+            Some(set)
+        }
+    }
+
+    /// Whether a single GTID (`uuid:number`) is in the set
+    pub fn contains(&self, gtid: &str) -> bool {
+        let Some((uuid, number)) = split_gtid(gtid) else {
+            return false;
+        };
+
+        self.intervals.get(&uuid).is_some_and(|ranges| {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&number))
+        })
+    }
+
+    /// Add a single GTID (`uuid:number`), returns false when it is not a GTID
+    pub fn add(&mut self, gtid: &str) -> bool {
+        let Some((uuid, number)) = split_gtid(gtid) else {
+            return false;
+        };
+
+        let ranges = self.intervals.entry(uuid).or_default();
+        ranges.push((number, number));
+        normalize(ranges);
+        true
+    }
+
+    /// Whether every GTID of the other set is in this one
+    pub fn includes(&self, other: &GtidSet) -> bool {
+        other.intervals.iter().all(|(uuid, ranges)| {
+            let own = self.intervals.get(uuid).map_or(&[][..], Vec::as_slice);
+            // Intervals are merged, so each one of the other set lies within a single own one
+            ranges.iter().all(|(start, end)| {
+                own.iter()
+                    .any(|(own_start, own_end)| own_start <= start && end <= own_end)
+            })
+        })
+    }
+
+    /// Source UUIDs with their sorted, merged intervals
+    pub fn sources(&self) -> impl Iterator<Item = (&str, &[(u64, u64)])> {
+        self.intervals
+            .iter()
+            .map(|(uuid, ranges)| (uuid.as_str(), ranges.as_slice()))
+    }
+}
+
+impl fmt::Display for GtidSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sources: Vec<String> = self
+            .intervals
+            .iter()
+            .map(|(uuid, ranges)| {
+                let mut source = uuid.clone();
+                for (start, end) in ranges {
+                    if start == end {
+                        source.push_str(&format!(":{}", start));
+                    } else {
+                        source.push_str(&format!(":{}-{}", start, end));
+                    }
+                }
+                source
+            })
+            .collect();
+        write!(f, "{}", sources.join(","))
+    }
+}
+
+fn split_gtid(gtid: &str) -> Option<(String, u64)> {
+    let (uuid, number) = gtid.rsplit_once(':')?;
+    Some((uuid.to_ascii_lowercase(), number.parse().ok()?))
+}
+
+/// Sort the intervals and merge overlapping and adjacent ones
+fn normalize(ranges: &mut Vec<(u64, u64)>) {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges.drain(..) {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    *ranges = merged;
+}
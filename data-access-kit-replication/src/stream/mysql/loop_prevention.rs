@@ -0,0 +1,99 @@
+/// Drops changes a consumer wrote back itself, so that bidirectional sync does not loop
+///
+/// Transactions are recognized by the server that made them, their GTID source, a write
+/// to a marker table, or a marker in the statement text of Rows_query events.
+#[derive(Debug, Clone, Default)]
+pub struct LoopPrevention {
+    /// Originating server IDs whose changes are dropped
+    pub server_ids: Vec<u32>,
+    /// GTID source UUIDs (lowercase) whose transactions are dropped (MySQL only)
+    pub gtid_sources: Vec<String>,
+    /// Rows of a transaction are dropped from its first write to this table on, rows written
+    /// before it are not held back, so the marker write has to be the first statement
+    pub marker_table: Option<(String, String)>,
+    /// Rows of statements whose text contains this are dropped (MySQL, Rows_query events)
+    pub query_marker: Option<String>,
+    ignoring_transaction: bool,
+    ignoring_statement: bool,
+}
+
+impl LoopPrevention {
+    pub fn is_enabled(&self) -> bool {
+        !self.server_ids.is_empty()
+            || !self.gtid_sources.is_empty()
+            || self.marker_table.is_some()
+            || self.query_marker.is_some()
+    }
+
+    /// A transaction with the GTID (`uuid:number`) starts
+    pub fn start_transaction(&mut self, gtid: &str) {
+        self.ignoring_statement = false;
+        self.ignoring_transaction = gtid.rsplit_once(':').is_some_and(|(uuid, _)| {
+            self.gtid_sources
+                .iter()
+                .any(|source| source.eq_ignore_ascii_case(uuid))
+        });
+    }
+
+    /// The transaction in progress was committed
+    pub fn end_transaction(&mut self) {
+        self.ignoring_transaction = false;
+        self.ignoring_statement = false;
+    }
+
+    /// Statement text of the following rows events, from a Rows_query event
+    pub fn start_statement(&mut self, query: &str) {
+        self.ignoring_statement = self
+            .query_marker
+            .as_ref()
+            .is_some_and(|marker| query.contains(marker.as_str()));
+    }
+
+    /// Whether to drop a rows event of the table made on the server
+    pub fn ignores_rows(&mut self, server_id: u32, schema: &str, table: &str) -> bool {
+        if let Some((marker_schema, marker_table)) = &self.marker_table {
+            if marker_schema == schema && marker_table == table {
+                self.ignoring_transaction = true;
+            }
+        }
+
+        self.ignoring_transaction || self.ignoring_statement || self.server_ids.contains(&server_id)
+    }
+
+    pub fn parse_server_ids(key: &str, value: &str) -> Result<Vec<u32>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|server_id| !server_id.is_empty())
+            .map(|server_id| {
+                server_id.parse::<u32>().map_err(|_| {
+                    format!(
+                        "Invalid {} '{}': expected comma separated server IDs",
+                        key, value
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn parse_gtid_sources(value: &str) -> Vec<String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|uuid| !uuid.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect()
+    }
+
+    pub fn parse_marker_table(key: &str, value: &str) -> Result<(String, String), String> {
+        match value.split_once('.') {
+            Some((schema, table)) if !schema.is_empty() && !table.is_empty() => {
+                Ok((schema.to_string(), table.to_string()))
+            }
+            _ => Err(format!(
+                "Invalid {} '{}': expected <schema>.<table>",
+                key, value
+            )),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use mysql_async::prelude::Queryable;
+use mysql_async::{Pool, Row};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Event type of MariaDB's GTID event, starts every transaction
+pub const MARIADB_GTID_EVENT: u8 = 162;
+
+/// MariaDB GTID, `domain-server-sequence`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MariaDbGtid {
+    pub domain_id: u32,
+    pub server_id: u32,
+    pub sequence: u64,
+}
+
+impl MariaDbGtid {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().splitn(3, '-');
+        Some(MariaDbGtid {
+            domain_id: parts.next()?.parse().ok()?,
+            server_id: parts.next()?.parse().ok()?,
+            sequence: parts.next()?.parse().ok()?,
+        })
+    }
+
+    /// GTID of a GTID event, the body starts with the sequence number and the domain id
+    pub fn from_event(server_id: u32, body: &[u8]) -> Option<Self> {
+        Some(MariaDbGtid {
+            domain_id: u32::from_le_bytes(body.get(8..12)?.try_into().ok()?),
+            server_id,
+            sequence: u64::from_le_bytes(body.get(0..8)?.try_into().ok()?),
+        })
+    }
+}
+
+impl fmt::Display for MariaDbGtid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.domain_id, self.server_id, self.sequence)
+    }
+}
+
+/// MariaDB GTID position, the last GTID of every replication domain, e.g. `0-1-1234,1-2-99`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MariaDbGtidPosition {
+    domains: BTreeMap<u32, MariaDbGtid>,
+}
+
+impl MariaDbGtidPosition {
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut position = MariaDbGtidPosition::default();
+        for gtid in value.split(',').filter(|gtid| !gtid.trim().is_empty()) {
+            position.advance(MariaDbGtid::parse(gtid)?);
+        }
+        Some(position)
+    }
+
+    /// Record a transaction as the last one of its domain
+    pub fn advance(&mut self, gtid: MariaDbGtid) {
+        self.domains.insert(gtid.domain_id, gtid);
+    }
+
+    /// Whether the transaction is at or before the position
+    pub fn covers(&self, gtid: &MariaDbGtid) -> bool {
+        self.domains
+            .get(&gtid.domain_id)
+            .is_some_and(|last| last.sequence >= gtid.sequence)
+    }
+
+    /// Whether every domain of the other position is at or before this one
+    pub fn covers_position(&self, other: &MariaDbGtidPosition) -> bool {
+        other.domains.values().all(|gtid| self.covers(gtid))
+    }
+}
+
+impl fmt::Display for MariaDbGtidPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gtids: Vec<String> = self.domains.values().map(|gtid| gtid.to_string()).collect();
+        write!(f, "{}", gtids.join(","))
+    }
+}
+
+/// GTID position of the binlog right before the binlog position
+pub async fn binlog_gtid_pos(
+    pool: &Pool,
+    file: &str,
+    position: u64,
+) -> Result<MariaDbGtidPosition, String> {
+    let mut conn = pool
+        .get_conn()
+        .await
+        .map_err(|e| format!("Failed to get connection for GTID: {}", e))?;
+
+    let gtid_pos: Option<String> = conn
+        .exec_first("SELECT BINLOG_GTID_POS(?, ?)", (file, position))
+        .await
+        .map_err(|e| format!("Failed to query BINLOG_GTID_POS: {}", e))?
+        .flatten();
+
+    gtid_pos
+        .as_deref()
+        .and_then(MariaDbGtidPosition::parse)
+        .ok_or_else(|| format!("No GTID position for {}:{}", file, position))
+}
+
+/// GTID position at the start of the binlog file, from its Gtid_list event
+pub async fn gtid_list(pool: &Pool, file: &str) -> Result<MariaDbGtidPosition, String> {
+    let mut conn = pool
+        .get_conn()
+        .await
+        .map_err(|e| format!("Failed to get connection for binlog events: {}", e))?;
+
+    // Gtid_list directly follows the format description event at the start of the file
+    let rows: Vec<Row> = conn
+        .exec("SHOW BINLOG EVENTS IN ? LIMIT 3", (file,))
+        .await
+        .map_err(|e| format!("Failed to query binlog events of {}: {}", file, e))?;
+
+    let info = rows
+        .iter()
+        .find(|row| row.get::<String, _>("Event_type").as_deref() == Some("Gtid_list"))
+        .and_then(|row| row.get::<String, _>("Info"))
+        .unwrap_or_default();
+
+    MariaDbGtidPosition::parse(info.trim_start_matches('[').trim_end_matches(']'))
+        .ok_or_else(|| format!("Invalid Gtid_list '{}' in binlog file {}", info, file))
+}
@@ -0,0 +1,291 @@
+use super::loop_prevention::LoopPrevention;
+use super::start_position::StartPosition;
+use super::stop_condition::StopCondition;
+use mysql_async::{ClientIdentity, SslOpts};
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+/// Stream options passed as connection URL query parameters
+#[derive(Debug, Clone)]
+pub struct MySQLStreamOptions {
+    /// End iteration when no event arrives within this time, next() resumes reading
+    pub idle_timeout: Option<Duration>,
+    /// Read binlog events on a background thread, buffering up to this many events
+    pub prefetch: Option<usize>,
+    /// Ask the server to send a heartbeat after this many seconds without events
+    pub heartbeat_interval: u64,
+    /// Fail the binlog connection when no data arrives within this many seconds
+    pub read_timeout: u64,
+    /// Give up connecting (metadata and binlog connections) after this time
+    pub connect_timeout: Option<Duration>,
+    /// Enable TCP keepalive probes on the metadata and binlog connections
+    pub tcp_keepalive: Option<Duration>,
+    /// Yield HeartbeatEvent objects from iteration
+    pub heartbeat_events: bool,
+    /// TLS settings of the connections to the server
+    pub ssl: MySQLSslOptions,
+    /// Unix socket path of the metadata and binlog connections, instead of TCP
+    pub socket: Option<String>,
+    /// Where to start when there is no checkpoint
+    pub start: StartPosition,
+    /// Format of the checkpoints the stream generates
+    pub checkpoint_format: CheckpointFormat,
+    /// Refuse MySQL without gtid_mode=ON instead of falling back to file/position checkpoints
+    pub require_gtid: bool,
+    /// Refuse replicas that do not write replicated changes to their binlog
+    pub require_log_replica_updates: bool,
+    /// Changes dropped before they reach PHP
+    pub loop_prevention: LoopPrevention,
+    /// Where to start instead when the checkpoint is gone from the server, `None` throws
+    pub on_checkpoint_unavailable: Option<StartPosition>,
+    /// End iteration at this point of the binlog
+    pub until: Option<StopCondition>,
+    /// End iteration after this many row events
+    pub max_events: Option<u64>,
+}
+
+/// Checkpoint format, `gtid:...` or `file:...`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckpointFormat {
+    /// GTID on MySQL, binlog file and position on MariaDB
+    #[default]
+    Auto,
+    Gtid,
+    File,
+}
+
+/// TLS mode, same meaning as the mysql client's --ssl-mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum SslMode {
+    #[default]
+    Disabled,
+    Preferred,
+    Required,
+    VerifyCa,
+    VerifyIdentity,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MySQLSslOptions {
+    pub mode: SslMode,
+    pub ca: Option<PathBuf>,
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    pub accept_invalid_certs: bool,
+}
+
+impl MySQLSslOptions {
+    /// TLS options for mysql_async, None when TLS is disabled
+    pub fn to_ssl_opts(&self) -> Option<SslOpts> {
+        if self.mode == SslMode::Disabled {
+            return None;
+        }
+
+        // REQUIRED and PREFERRED encrypt without verifying the server, like the mysql client
+        let verify_ca = self.mode >= SslMode::VerifyCa && !self.accept_invalid_certs;
+        let verify_identity = self.mode == SslMode::VerifyIdentity;
+
+        let identity = match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => Some(ClientIdentity::new(cert.clone(), key.clone())),
+            _ => None,
+        };
+
+        Some(
+            SslOpts::default()
+                .with_root_cert_path(self.ca.clone())
+                .with_client_identity(identity)
+                .with_danger_accept_invalid_certs(!verify_ca)
+                .with_danger_skip_domain_validation(!verify_identity),
+        )
+    }
+}
+
+impl Default for MySQLStreamOptions {
+    fn default() -> Self {
+        MySQLStreamOptions {
+            idle_timeout: None,
+            prefetch: None,
+            heartbeat_interval: 30,
+            read_timeout: 60,
+            connect_timeout: None,
+            tcp_keepalive: None,
+            heartbeat_events: false,
+            ssl: MySQLSslOptions::default(),
+            socket: None,
+            start: StartPosition::default(),
+            checkpoint_format: CheckpointFormat::default(),
+            require_gtid: true,
+            require_log_replica_updates: true,
+            loop_prevention: LoopPrevention::default(),
+            on_checkpoint_unavailable: None,
+            until: None,
+            max_events: None,
+        }
+    }
+}
+
+impl MySQLStreamOptions {
+    pub fn from_url(url: &Url) -> Result<Self, String> {
+        let mut options = MySQLStreamOptions::default();
+
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "idle_timeout" => options.idle_timeout = Some(parse_seconds(&key, &value)?),
+                "prefetch" => options.prefetch = parse_buffer_size(&key, &value)?,
+                "heartbeat_interval" => {
+                    options.heartbeat_interval = parse_whole_seconds(&key, &value)?
+                }
+                "read_timeout" => options.read_timeout = parse_whole_seconds(&key, &value)?,
+                "connect_timeout" => options.connect_timeout = Some(parse_seconds(&key, &value)?),
+                "tcp_keepalive" => {
+                    options.tcp_keepalive =
+                        Some(parse_seconds(&key, &value)?).filter(|interval| !interval.is_zero())
+                }
+                "socket" => options.socket = Some(value.to_string()).filter(|s| !s.is_empty()),
+                "heartbeat_events" => options.heartbeat_events = parse_bool(&key, &value)?,
+                "ssl-mode" => options.ssl.mode = parse_ssl_mode(&key, &value)?,
+                "ssl-ca" => options.ssl.ca = Some(PathBuf::from(value.as_ref())),
+                "ssl-cert" => options.ssl.cert = Some(PathBuf::from(value.as_ref())),
+                "ssl-key" => options.ssl.key = Some(PathBuf::from(value.as_ref())),
+                "ssl-accept-invalid-certs" => {
+                    options.ssl.accept_invalid_certs = parse_bool(&key, &value)?
+                }
+                "start" => options.start = StartPosition::parse(&key, &value)?,
+                "require_gtid" => options.require_gtid = parse_bool(&key, &value)?,
+                "ignore_server_ids" => {
+                    options.loop_prevention.server_ids =
+                        LoopPrevention::parse_server_ids(&key, &value)?
+                }
+                "ignore_gtid_sources" => {
+                    options.loop_prevention.gtid_sources =
+                        LoopPrevention::parse_gtid_sources(&value)
+                }
+                "ignore_marker_table" => {
+                    options.loop_prevention.marker_table =
+                        Some(LoopPrevention::parse_marker_table(&key, &value)?)
+                }
+                "ignore_query_marker" => {
+                    options.loop_prevention.query_marker =
+                        Some(value.to_string()).filter(|marker| !marker.is_empty())
+                }
+                "require_log_replica_updates" => {
+                    options.require_log_replica_updates = parse_bool(&key, &value)?
+                }
+                "checkpoint_format" => {
+                    options.checkpoint_format = match value.as_ref() {
+                        "auto" => CheckpointFormat::Auto,
+                        "gtid" => CheckpointFormat::Gtid,
+                        "file" => CheckpointFormat::File,
+                        _ => {
+                            return Err(format!(
+                                "Invalid {} '{}': expected auto, gtid or file",
+                                key, value
+                            ))
+                        }
+                    }
+                }
+                "on_checkpoint_unavailable" => {
+                    options.on_checkpoint_unavailable = match value.as_ref() {
+                        "throw" => None,
+                        "earliest" => Some(StartPosition::Earliest),
+                        "latest" => Some(StartPosition::Latest),
+                        _ => {
+                            return Err(format!(
+                                "Invalid {} '{}': expected throw, earliest or latest",
+                                key, value
+                            ))
+                        }
+                    }
+                }
+                "until" => options.until = Some(StopCondition::parse(&key, &value)?),
+                "max_events" => {
+                    options.max_events = match value.parse::<u64>() {
+                        Ok(max_events) if max_events > 0 => Some(max_events),
+                        _ => {
+                            return Err(format!(
+                                "Invalid {} '{}': expected a positive integer",
+                                key, value
+                            ))
+                        }
+                    }
+                }
+                // Other parameters (e.g. server_id) are handled by the stream itself
+                _ => {}
+            }
+        }
+
+        // A quiet stream must see a heartbeat before the read times out
+        if options.heartbeat_interval == 0 || options.heartbeat_interval >= options.read_timeout {
+            return Err(format!(
+                "Invalid heartbeat_interval '{}': expected at least 1 and less than read_timeout ({})",
+                options.heartbeat_interval, options.read_timeout
+            ));
+        }
+
+        if options.ssl.cert.is_some() != options.ssl.key.is_some() {
+            return Err("ssl-cert and ssl-key must be given together".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_seconds(key: &str, value: &str) -> Result<Duration, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| {
+            format!(
+                "Invalid {} '{}': expected a non-negative number of seconds",
+                key, value
+            )
+        })
+}
+
+fn parse_whole_seconds(key: &str, value: &str) -> Result<u64, String> {
+    value.parse::<u64>().map_err(|_| {
+        format!(
+            "Invalid {} '{}': expected a whole number of seconds",
+            key, value
+        )
+    })
+}
+
+fn parse_ssl_mode(key: &str, value: &str) -> Result<SslMode, String> {
+    match value.to_ascii_uppercase().as_str() {
+        "DISABLED" => Ok(SslMode::Disabled),
+        "PREFERRED" => Ok(SslMode::Preferred),
+        "REQUIRED" => Ok(SslMode::Required),
+        "VERIFY_CA" => Ok(SslMode::VerifyCa),
+        "VERIFY_IDENTITY" => Ok(SslMode::VerifyIdentity),
+        _ => Err(format!(
+            "Invalid {} '{}': expected DISABLED, PREFERRED, REQUIRED, VERIFY_CA or VERIFY_IDENTITY",
+            key, value
+        )),
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(format!(
+            "Invalid {} '{}': expected true, false, 1 or 0",
+            key, value
+        )),
+    }
+}
+
+/// Parse a buffer size, 0 disables buffering
+fn parse_buffer_size(key: &str, value: &str) -> Result<Option<usize>, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Ok(None),
+        Ok(size) => Ok(Some(size)),
+        Err(_) => Err(format!(
+            "Invalid {} '{}': expected a non-negative integer",
+            key, value
+        )),
+    }
+}
@@ -0,0 +1,166 @@
+use super::binlog_connection::{BinlogConnection, BinlogConnectionOpts, BinlogEvent};
+use super::with_connect_timeout;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::task::Poll;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, Receiver};
+use tokio::sync::oneshot;
+
+type PrefetchedEvent = Result<BinlogEvent, String>;
+
+/// Reads events from a binlog stream with an optional deadline
+///
+/// Events are read either directly on the calling thread, or by a background
+/// thread that reads ahead into a bounded buffer.
+pub enum BinlogReader {
+    Direct(BinlogConnection),
+    Prefetch(PrefetchReader),
+}
+
+impl BinlogReader {
+    pub fn direct(connection: BinlogConnection) -> Self {
+        BinlogReader::Direct(connection)
+    }
+
+    pub async fn prefetch(
+        opts: BinlogConnectionOpts,
+        buffer_size: usize,
+        connect_timeout: Option<Duration>,
+    ) -> Result<Self, String> {
+        PrefetchReader::spawn(opts, buffer_size, connect_timeout)
+            .await
+            .map(BinlogReader::Prefetch)
+    }
+
+    /// Events read ahead and waiting in the prefetch buffer
+    pub fn buffered(&self) -> usize {
+        match self {
+            BinlogReader::Direct(_) => 0,
+            BinlogReader::Prefetch(reader) => reader.receiver.len(),
+        }
+    }
+
+    /// Read the next event, returns None if the deadline passed before it arrived
+    pub async fn read(&mut self, deadline: Option<Instant>) -> Result<Option<BinlogEvent>, String> {
+        match self {
+            BinlogReader::Direct(connection) => connection.read(deadline).await,
+            BinlogReader::Prefetch(reader) => reader.read(deadline).await,
+        }
+    }
+}
+
+/// Reads and parses events on a background thread
+///
+/// The thread blocks when the buffer is full, so it never gets further ahead than
+/// buffer_size events. Dropping the reader stops the thread and closes the stream.
+///
+/// The connection is opened on the thread's own runtime, which keeps driving it while
+/// the PHP thread is busy; a connection opened on the PHP thread's runtime would only
+/// make progress while PHP waits for the next event.
+pub struct PrefetchReader {
+    receiver: Receiver<PrefetchedEvent>,
+    _thread: JoinHandle<()>,
+}
+
+impl PrefetchReader {
+    async fn spawn(
+        opts: BinlogConnectionOpts,
+        buffer_size: usize,
+        connect_timeout: Option<Duration>,
+    ) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::channel::<PrefetchedEvent>(buffer_size);
+        let (connected_sender, connected) = oneshot::channel::<Result<(), String>>();
+
+        let thread = thread::Builder::new()
+            .name("binlog-prefetch".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = connected_sender.send(Err(format!(
+                            "Failed to create Tokio runtime for prefetch: {}",
+                            e
+                        )));
+                        return;
+                    }
+                };
+
+                runtime.block_on(async move {
+                    let mut connection =
+                        match with_connect_timeout(connect_timeout, BinlogConnection::open(&opts))
+                            .await
+                        {
+                            Ok(connection) => connection,
+                            Err(e) => {
+                                let _ = connected_sender.send(Err(e));
+                                return;
+                            }
+                        };
+                    if connected_sender.send(Ok(())).is_err() {
+                        return;
+                    }
+
+                    loop {
+                        // Stop reading as soon as the receiving side goes away
+                        let mut read = pin!(connection.read(None));
+                        let mut closed = pin!(sender.closed());
+                        let result = poll_fn(|cx| {
+                            if let Poll::Ready(result) = read.as_mut().poll(cx) {
+                                return Poll::Ready(Some(result));
+                            }
+                            if closed.as_mut().poll(cx).is_ready() {
+                                return Poll::Ready(None);
+                            }
+                            Poll::Pending
+                        })
+                        .await;
+
+                        let event = match result {
+                            Some(Ok(event)) => event
+                                .ok_or_else(|| "Binlog read ended without an event".to_string()),
+                            Some(Err(e)) => Err(e),
+                            None => return,
+                        };
+                        let failed = event.is_err();
+
+                        if sender.send(event).await.is_err() || failed {
+                            return;
+                        }
+                    }
+                });
+            })
+            .map_err(|e| format!("Failed to start prefetch thread: {}", e))?;
+
+        connected
+            .await
+            .map_err(|_| "Prefetch thread stopped".to_string())??;
+
+        Ok(PrefetchReader {
+            receiver,
+            _thread: thread,
+        })
+    }
+
+    async fn read(&mut self, deadline: Option<Instant>) -> Result<Option<BinlogEvent>, String> {
+        // Receiving from the channel is cancel-safe, no event is lost on timeout
+        let received = match deadline {
+            Some(deadline) => {
+                match tokio::time::timeout_at(deadline.into(), self.receiver.recv()).await {
+                    Ok(received) => received,
+                    Err(_) => return Ok(None),
+                }
+            }
+            None => self.receiver.recv().await,
+        };
+
+        match received {
+            Some(event) => event.map(Some),
+            None => Err("Prefetch thread stopped".to_string()),
+        }
+    }
+}
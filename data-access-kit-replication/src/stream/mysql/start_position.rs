@@ -0,0 +1,207 @@
+use super::binlog_connection::{BinlogConnection, BinlogConnectionOpts, DumpPosition};
+use super::mariadb_gtid::{self, MariaDbGtidPosition};
+use mysql_async::prelude::Queryable;
+use mysql_async::{Pool, Row};
+
+/// Where a stream starts reading when the checkpointer has no checkpoint
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum StartPosition {
+    /// Current end of the binlog, only new changes are streamed
+    #[default]
+    Latest,
+    /// Oldest binlog file still retained by the server
+    Earliest,
+    /// First event at or after the unix timestamp
+    Timestamp(i64),
+    /// Explicit checkpoint string (gtid:... or file:...)
+    Checkpoint(String),
+}
+
+impl StartPosition {
+    pub fn parse(key: &str, value: &str) -> Result<Self, String> {
+        if value == "latest" {
+            Ok(StartPosition::Latest)
+        } else if value == "earliest" {
+            Ok(StartPosition::Earliest)
+        } else if let Some(timestamp) = value.strip_prefix("timestamp:") {
+            timestamp
+                .parse::<i64>()
+                .map(StartPosition::Timestamp)
+                .map_err(|_| {
+                    format!(
+                        "Invalid {} '{}': expected timestamp:<unix timestamp>",
+                        key, value
+                    )
+                })
+        } else if value.starts_with("gtid:") || value.starts_with("file:") {
+            Ok(StartPosition::Checkpoint(value.to_string()))
+        } else {
+            Err(format!(
+                "Invalid {} '{}': expected latest, earliest, timestamp:<unix timestamp> or a checkpoint",
+                key, value
+            ))
+        }
+    }
+}
+
+/// Binlog files retained by the server, oldest first
+pub async fn binlog_files(pool: &Pool) -> Result<Vec<String>, String> {
+    let mut conn = pool
+        .get_conn()
+        .await
+        .map_err(|e| format!("Failed to get connection for binlog files: {}", e))?;
+
+    let rows: Vec<Row> = conn
+        .query("SHOW BINARY LOGS")
+        .await
+        .map_err(|e| format!("Failed to query binary logs: {}", e))?;
+
+    let files: Vec<String> = rows
+        .iter()
+        .filter_map(|row| row.get::<String, _>("Log_name"))
+        .collect();
+
+    if files.is_empty() {
+        return Err("No binary logs available - is binary logging enabled?".to_string());
+    }
+
+    Ok(files)
+}
+
+/// GTID set executed before the binlog file, from its Previous_gtids event (MySQL only)
+pub async fn previous_gtids(pool: &Pool, file: &str) -> Result<String, String> {
+    let mut conn = pool
+        .get_conn()
+        .await
+        .map_err(|e| format!("Failed to get connection for binlog events: {}", e))?;
+
+    // Previous_gtids directly follows the format description event at the start of the file
+    let rows: Vec<Row> = conn
+        .exec("SHOW BINLOG EVENTS IN ? LIMIT 3", (file,))
+        .await
+        .map_err(|e| format!("Failed to query binlog events of {}: {}", file, e))?;
+
+    Ok(rows
+        .iter()
+        .find(|row| row.get::<String, _>("Event_type").as_deref() == Some("Previous_gtids"))
+        .and_then(|row| row.get::<String, _>("Info"))
+        .unwrap_or_default()
+        .replace('\n', ""))
+}
+
+/// Timestamp of the first event in the binlog file, i.e. when the server started writing it
+pub async fn binlog_start_timestamp(
+    opts: &BinlogConnectionOpts,
+    file: &str,
+) -> Result<i64, String> {
+    let opts = BinlogConnectionOpts {
+        position: DumpPosition::File(file.to_string(), 4),
+        ..opts.clone()
+    };
+    let mut connection = BinlogConnection::open(&opts)
+        .await
+        .map_err(|e| format!("Failed to open binlog file {}: {}", file, e))?;
+
+    // The server starts with an artificial rotate event without a timestamp
+    for _ in 0..3 {
+        let event = connection
+            .read(None)
+            .await
+            .map_err(|e| format!("Failed to read binlog file {}: {}", file, e))?
+            .ok_or_else(|| format!("No event in binlog file {}", file))?;
+        if event.header.timestamp != 0 {
+            return Ok(event.header.timestamp as i64);
+        }
+    }
+
+    Err(format!(
+        "No timestamped event at the start of binlog file {}",
+        file
+    ))
+}
+
+/// Why the server can no longer stream from a checkpoint, `None` when it can
+pub async fn checkpoint_unavailable(
+    pool: &Pool,
+    checkpoint: &str,
+    mariadb: bool,
+) -> Result<Option<String>, String> {
+    let mut conn = pool
+        .get_conn()
+        .await
+        .map_err(|e| format!("Failed to get connection for checkpoint validation: {}", e))?;
+
+    if let (Some(gtid_pos), true) = (checkpoint.strip_prefix("gtid:"), mariadb) {
+        let Some(gtid_position) = MariaDbGtidPosition::parse(gtid_pos) else {
+            return Ok(Some(
+                "it is not a MariaDB GTID position (domain-server-sequence)".to_string(),
+            ));
+        };
+
+        let binlog_pos: String = conn
+            .query_first("SELECT @@GLOBAL.gtid_binlog_pos")
+            .await
+            .map_err(|e| format!("Failed to query gtid_binlog_pos: {}", e))?
+            .unwrap_or_default();
+        if !MariaDbGtidPosition::parse(&binlog_pos)
+            .unwrap_or_default()
+            .covers_position(&gtid_position)
+        {
+            return Ok(Some(format!(
+                "it contains transactions the server has not executed (gtid_binlog_pos is '{}'), \
+                 was it saved on a different server?",
+                binlog_pos
+            )));
+        }
+
+        let files = binlog_files(pool).await?;
+        let oldest = mariadb_gtid::gtid_list(pool, &files[0]).await?;
+        if !gtid_position.covers_position(&oldest) {
+            return Ok(Some(format!(
+                "transactions after it were purged from the binlog (the oldest binlog file {} \
+                 starts at '{}')",
+                files[0], oldest
+            )));
+        }
+    } else if let Some(gtid_set) = checkpoint.strip_prefix("gtid:") {
+        // Streaming skips the checkpoint's GTID set, the server must still have everything else
+        let (purged_covered, executed, gtid_purged): (bool, bool, String) = conn
+            .exec_first(
+                "SELECT GTID_SUBSET(@@GLOBAL.gtid_purged, ?), \
+                 GTID_SUBSET(?, @@GLOBAL.gtid_executed), @@GLOBAL.gtid_purged",
+                (gtid_set, gtid_set),
+            )
+            .await
+            .map_err(|e| format!("Failed to query gtid_purged: {}", e))?
+            .unwrap_or((true, true, String::new()));
+
+        if !executed {
+            return Ok(Some(
+                "it contains transactions the server has not executed, \
+                 was it saved on a different server?"
+                    .to_string(),
+            ));
+        }
+        if !purged_covered {
+            return Ok(Some(format!(
+                "transactions after it were purged from the binlog (gtid_purged is '{}')",
+                gtid_purged.replace('\n', "")
+            )));
+        }
+    } else if let Some((file, _)) = checkpoint
+        .strip_prefix("file:")
+        .and_then(|file_position| file_position.rsplit_once(':'))
+    {
+        let files = binlog_files(pool).await?;
+        if !files.iter().any(|retained| retained == file) {
+            return Ok(Some(format!(
+                "binlog file '{}' is not on the server, retained files are '{}' to '{}'",
+                file,
+                files[0],
+                files[files.len() - 1]
+            )));
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,247 @@
+use ext_php_rs::prelude::*;
+use ext_php_rs::types::{ZendHashTable, Zval};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Prefix of metric names in OpenMetrics output
+const METRIC_PREFIX: &str = "data_access_kit_replication";
+
+/// Counters of a stream, kept across disconnect() and reconnects
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    /// Binlog events of any type read from the server
+    pub events_read: u64,
+    /// Row events handed to PHP
+    pub events_emitted: u64,
+    /// Row events handed to PHP by event type
+    pub events_by_type: BTreeMap<String, u64>,
+    /// Row events handed to PHP by schema and table
+    pub events_by_table: BTreeMap<(String, String), u64>,
+    /// Rows events rejected by the filter or dropped by loop prevention
+    pub events_filtered: u64,
+    /// Rows handled by the error policy instead of being emitted
+    pub rows_failed: u64,
+    /// Size of the binlog events read
+    pub bytes_read: u64,
+    /// Binlog connections reopened after the connection was lost
+    pub reconnects: u64,
+    /// Checkpoints saved through the checkpointer
+    pub checkpoint_saves: u64,
+    /// Binlog timestamp of the last event header read
+    pub last_event_timestamp: Option<i64>,
+    /// A heartbeat arrived or a read found nothing after the last event read, the stream is caught up
+    pub caught_up: bool,
+    /// Time the last heartbeat arrived
+    pub last_heartbeat: Option<f64>,
+}
+
+impl StreamStats {
+    pub fn record_read(&mut self, timestamp: u32) {
+        // Artificial events (rotate on connect, heartbeats) carry no timestamp
+        if timestamp > 0 {
+            self.last_event_timestamp = Some(timestamp as i64);
+            self.caught_up = false;
+        }
+    }
+
+    pub fn record_heartbeat(&mut self) {
+        self.last_heartbeat = Some(unix_time());
+        // Heartbeats are sent only when the server has nothing else to send
+        self.caught_up = true;
+    }
+
+    /// A read waited without receiving an event, the server has nothing else to send
+    pub fn record_idle(&mut self) {
+        self.caught_up = true;
+    }
+
+    pub fn record_emitted(&mut self, event_type: &str, schema: &str, table: &str) {
+        self.events_emitted += 1;
+        *self
+            .events_by_type
+            .entry(event_type.to_string())
+            .or_default() += 1;
+        *self
+            .events_by_table
+            .entry((schema.to_string(), table.to_string()))
+            .or_default() += 1;
+    }
+
+    /// Seconds between the binlog timestamp of the last event read and now
+    pub fn seconds_behind_source(&self) -> Option<i64> {
+        if self.caught_up {
+            return Some(0);
+        }
+        self.last_event_timestamp
+            .map(|timestamp| (unix_time() as i64 - timestamp).max(0))
+    }
+
+    /// Counters as a PHP array, with the events currently waiting in the prefetch buffer
+    pub fn to_array(&self, checkpoint: Option<&str>, prefetch_buffered: usize) -> PhpResult<Zval> {
+        let mut stats = ZendHashTable::new();
+        stats.insert("eventsRead", self.events_read as i64)?;
+        stats.insert("eventsEmitted", self.events_emitted as i64)?;
+        stats.insert("eventsFiltered", self.events_filtered as i64)?;
+        stats.insert("rowsFailed", self.rows_failed as i64)?;
+        stats.insert("bytesRead", self.bytes_read as i64)?;
+        stats.insert("reconnects", self.reconnects as i64)?;
+        stats.insert("checkpointSaves", self.checkpoint_saves as i64)?;
+        stats.insert("checkpoint", checkpoint.map(str::to_string))?;
+        stats.insert("secondsBehindSource", self.seconds_behind_source())?;
+        stats.insert("lastHeartbeat", self.last_heartbeat)?;
+        stats.insert("prefetchBuffered", prefetch_buffered as i64)?;
+
+        let mut zval = Zval::new();
+        zval.set_hashtable(stats);
+        Ok(zval)
+    }
+
+    /// Render counters and gauges in the OpenMetrics text format, every sample gets the labels
+    pub fn to_openmetrics(&self, labels: &[(&str, String)]) -> String {
+        let mut out = String::new();
+
+        let counter =
+            |out: &mut String, name: &str, help: &str, samples: &[(Vec<(&str, &str)>, u64)]| {
+                let _ = writeln!(out, "# TYPE {}_{} counter", METRIC_PREFIX, name);
+                let _ = writeln!(out, "# HELP {}_{} {}", METRIC_PREFIX, name, help);
+                for (extra, value) in samples {
+                    let _ = writeln!(
+                        out,
+                        "{}_{}_total{} {}",
+                        METRIC_PREFIX,
+                        name,
+                        format_labels(labels, extra),
+                        value
+                    );
+                }
+            };
+
+        counter(
+            &mut out,
+            "events_read",
+            "Binlog events read from the server.",
+            &[(vec![], self.events_read)],
+        );
+        counter(
+            &mut out,
+            "events_emitted",
+            "Row events handed to PHP by event type.",
+            &self
+                .events_by_type
+                .iter()
+                .map(|(event_type, count)| (vec![("type", event_type.as_str())], *count))
+                .collect::<Vec<_>>(),
+        );
+        counter(
+            &mut out,
+            "table_events",
+            "Row events handed to PHP by table.",
+            &self
+                .events_by_table
+                .iter()
+                .map(|((schema, table), count)| {
+                    (
+                        vec![("schema", schema.as_str()), ("table", table.as_str())],
+                        *count,
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+        counter(
+            &mut out,
+            "events_filtered",
+            "Rows events rejected by the filter.",
+            &[(vec![], self.events_filtered)],
+        );
+        counter(
+            &mut out,
+            "rows_failed",
+            "Rows handled by the error policy.",
+            &[(vec![], self.rows_failed)],
+        );
+        counter(
+            &mut out,
+            "read_bytes",
+            "Size of binlog events read.",
+            &[(vec![], self.bytes_read)],
+        );
+        counter(
+            &mut out,
+            "reconnects",
+            "Binlog connections reopened after the connection was lost.",
+            &[(vec![], self.reconnects)],
+        );
+        counter(
+            &mut out,
+            "checkpoint_saves",
+            "Checkpoints saved through the checkpointer.",
+            &[(vec![], self.checkpoint_saves)],
+        );
+
+        // Gauges without a value yet are left out
+        let gauge =
+            |out: &mut String, name: &str, unit: &str, help: &str, value: Option<String>| {
+                let _ = writeln!(out, "# TYPE {}_{} gauge", METRIC_PREFIX, name);
+                let _ = writeln!(out, "# UNIT {}_{} {}", METRIC_PREFIX, name, unit);
+                let _ = writeln!(out, "# HELP {}_{} {}", METRIC_PREFIX, name, help);
+                if let Some(value) = value {
+                    let _ = writeln!(
+                        out,
+                        "{}_{}{} {}",
+                        METRIC_PREFIX,
+                        name,
+                        format_labels(labels, &[]),
+                        value
+                    );
+                }
+            };
+
+        gauge(
+            &mut out,
+            "lag_seconds",
+            "seconds",
+            "Seconds between the binlog timestamp of the last event read and now.",
+            self.seconds_behind_source().map(|lag| lag.to_string()),
+        );
+        gauge(
+            &mut out,
+            "last_heartbeat_timestamp_seconds",
+            "seconds",
+            "Unix time the last heartbeat arrived.",
+            self.last_heartbeat.map(|time| format!("{:.3}", time)),
+        );
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// Render {name="value",...} with label values escaped
+fn format_labels(labels: &[(&str, String)], extra: &[(&str, &str)]) -> String {
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .chain(extra.iter().copied())
+        .map(|(name, value)| {
+            let escaped = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, escaped)
+        })
+        .collect();
+
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
@@ -0,0 +1,81 @@
+use super::gtid_set::GtidSet;
+
+/// Where a bounded replay ends, iteration finishes before the first event past it
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopCondition {
+    /// Server's binlog position when the stream starts, resolved to Position by start()
+    Current,
+    /// Binlog file and position
+    Position(String, u64),
+    /// Transactions in the GTID set (MySQL with GTID checkpoints)
+    Gtid(GtidSet),
+    /// Events up to the unix timestamp
+    Timestamp(i64),
+}
+
+impl StopCondition {
+    pub fn parse(key: &str, value: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid {} '{}': expected current, timestamp:<unix timestamp>, gtid:<GTID set> or file:<file>:<position>",
+                key, value
+            )
+        };
+
+        if value == "current" {
+            Ok(StopCondition::Current)
+        } else if let Some(timestamp) = value.strip_prefix("timestamp:") {
+            timestamp
+                .parse::<i64>()
+                .map(StopCondition::Timestamp)
+                .map_err(|_| invalid())
+        } else if let Some(gtid_set) = value.strip_prefix("gtid:") {
+            GtidSet::parse(gtid_set)
+                .map(StopCondition::Gtid)
+                .ok_or_else(invalid)
+        } else if let Some(file_position) = value.strip_prefix("file:") {
+            let (file, position) = file_position.rsplit_once(':').ok_or_else(invalid)?;
+            let position = position.parse::<u64>().map_err(|_| invalid())?;
+            Ok(StopCondition::Position(file.to_string(), position))
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// The binlog has been read up to the position
+    pub fn reached_at_position(&self, file: &str, position: u64) -> bool {
+        match self {
+            StopCondition::Position(stop_file, stop_position) => {
+                (file_order(file), position) >= (file_order(stop_file), *stop_position)
+            }
+            _ => false,
+        }
+    }
+
+    /// A rows event written at the timestamp is past the bound
+    pub fn reached_at_time(&self, timestamp: i64) -> bool {
+        matches!(self, StopCondition::Timestamp(stop) if timestamp > *stop)
+    }
+
+    /// Every transaction of the GTID set bound is among the ones read
+    pub fn reached_at_gtids(&self, read: &GtidSet) -> bool {
+        matches!(self, StopCondition::Gtid(set) if read.includes(set))
+    }
+
+    /// The server has nothing more to send before the heartbeat position and time
+    pub fn reached_at_heartbeat(&self, file: &str, position: u64, now: i64) -> bool {
+        self.reached_at_position(file, position) || self.reached_at_time(now)
+    }
+}
+
+/// Binlog file names share the base name, the sequence number outgrows its zero padding
+/// after .999999
+fn file_order(file: &str) -> (&str, u64) {
+    match file.rsplit_once('.') {
+        Some((base, number)) => match number.parse() {
+            Ok(number) => (base, number),
+            Err(_) => (file, 0),
+        },
+        None => (file, 0),
+    }
+}
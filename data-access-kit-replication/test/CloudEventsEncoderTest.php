@@ -0,0 +1,52 @@
+<?php
+
+namespace DataAccessKit\Replication\Test;
+
+use PHPUnit\Framework\Attributes\Group;
+use PHPUnit\Framework\TestCase;
+use DataAccessKit\Replication\{CloudEventsEncoder, EventInterface, UpdateEvent};
+
+#[Group("unit")]
+class CloudEventsEncoderTest extends TestCase
+{
+    public function testEncodeUpdateEvent(): void
+    {
+        $event = new UpdateEvent(
+            EventInterface::UPDATE,
+            1760000000,
+            'file:mysql-bin.000003:1500',
+            'mydb',
+            'users',
+            (object)['id' => 1, 'name' => 'John'],
+            (object)['id' => 1, 'name' => 'Jane', 'updated_at' => new \DateTimeImmutable('@1760000000')],
+            1,
+            'mysql-bin.000003',
+            1234,
+            null,
+            3
+        );
+
+        $cloudEvent = (new CloudEventsEncoder())->encode($event);
+
+        $this->assertSame('1.0', $cloudEvent['specversion']);
+        $this->assertSame('file:mysql-bin.000003:1500#1234.3', $cloudEvent['id']);
+        $this->assertSame('mysql://1/mydb', $cloudEvent['source']);
+        $this->assertSame('dataaccesskit.replication.users.update', $cloudEvent['type']);
+        $this->assertSame('users', $cloudEvent['subject']);
+        $this->assertSame('2025-10-09T08:53:20Z', $cloudEvent['time']);
+        $this->assertSame('application/json', $cloudEvent['datacontenttype']);
+        $this->assertSame(['id' => 1, 'name' => 'John'], $cloudEvent['data']['before']);
+        $this->assertSame('Jane', $cloudEvent['data']['after']['name']);
+        $this->assertSame('datetime', $cloudEvent['data']['after']['updated_at']['$type']);
+    }
+
+    public function testEncodeJsonWithTypePrefix(): void
+    {
+        $event = new UpdateEvent(EventInterface::UPDATE, 1760000000, 'checkpoint', 'mydb', 'users', (object)['id' => 1], (object)['id' => 2]);
+
+        $cloudEvent = json_decode((new CloudEventsEncoder('com.example.db'))->encodeJson($event), true);
+
+        $this->assertSame('com.example.db.users.update', $cloudEvent['type']);
+        $this->assertSame(['id' => 2], $cloudEvent['data']['after']);
+    }
+}
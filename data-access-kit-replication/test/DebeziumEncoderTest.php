@@ -0,0 +1,91 @@
+<?php
+
+namespace DataAccessKit\Replication\Test;
+
+use PHPUnit\Framework\Attributes\Group;
+use PHPUnit\Framework\TestCase;
+use DataAccessKit\Replication\{DebeziumEncoder, DeleteEvent, EventInterface, InsertEvent, UpdateEvent};
+
+#[Group("unit")]
+class DebeziumEncoderTest extends TestCase
+{
+    public function testEncodeInsertEvent(): void
+    {
+        $event = new InsertEvent(
+            EventInterface::INSERT,
+            1760000000,
+            'gtid:3E11FA47-71CA-11E1-9E33-C80AA9429562:1-23',
+            'mydb',
+            'users',
+            (object)[
+                'id' => 1,
+                'price' => '123.45',
+                'roles' => ['read', 'write'],
+                'tags' => ['read', 'write'],
+                'meta' => (object)['key' => 'value'],
+                'payload' => "\xff\x00",
+                'label' => 'abc',
+                'score' => 42,
+                'active' => true,
+            ],
+            1,
+            'mysql-bin.000003',
+            1234,
+            '3E11FA47-71CA-11E1-9E33-C80AA9429562:23',
+            2,
+            'INSERT INTO users (id) VALUES (1) /* request_id=42 */',
+            ['id' => 'long', 'roles' => 'set', 'tags' => 'json', 'label' => 'json', 'score' => 'json', 'active' => 'json'],
+        );
+
+        $envelope = (new DebeziumEncoder('dbserver1'))->encode($event);
+
+        $this->assertNull($envelope['before']);
+        $this->assertSame(1, $envelope['after']['id']);
+        $this->assertSame('123.45', $envelope['after']['price']);
+        $this->assertSame('read,write', $envelope['after']['roles']);
+        $this->assertSame('["read","write"]', $envelope['after']['tags']);
+        $this->assertSame('{"key":"value"}', $envelope['after']['meta']);
+        $this->assertSame(base64_encode("\xff\x00"), $envelope['after']['payload']);
+        $this->assertSame('"abc"', $envelope['after']['label']);
+        $this->assertSame('42', $envelope['after']['score']);
+        $this->assertSame('true', $envelope['after']['active']);
+        $this->assertSame('c', $envelope['op']);
+        $this->assertIsInt($envelope['ts_ms']);
+        $this->assertSame('dbserver1', $envelope['source']['name']);
+        $this->assertSame('mysql', $envelope['source']['connector']);
+        $this->assertSame('mydb', $envelope['source']['db']);
+        $this->assertSame('users', $envelope['source']['table']);
+        $this->assertSame(1, $envelope['source']['server_id']);
+        $this->assertSame('3E11FA47-71CA-11E1-9E33-C80AA9429562:23', $envelope['source']['gtid']);
+        $this->assertSame('mysql-bin.000003', $envelope['source']['file']);
+        $this->assertSame(1234, $envelope['source']['pos']);
+        $this->assertSame(2, $envelope['source']['row']);
+        $this->assertSame('INSERT INTO users (id) VALUES (1) /* request_id=42 */', $envelope['source']['query']);
+        $this->assertSame(1760000000000, $envelope['source']['ts_ms']);
+    }
+
+    public function testEncodeOperations(): void
+    {
+        $encoder = new DebeziumEncoder();
+        $insert = new InsertEvent(EventInterface::INSERT, 1760000000, 'checkpoint', 'mydb', 'users', (object)['id' => 1]);
+        $update = new UpdateEvent(EventInterface::UPDATE, 1760000000, 'checkpoint', 'mydb', 'users', (object)['id' => 1], (object)['id' => 2]);
+        $delete = new DeleteEvent(EventInterface::DELETE, 1760000000, 'checkpoint', 'mydb', 'users', (object)['id' => 1]);
+
+        $this->assertSame('r', $encoder->encode($insert, true)['op']);
+        $this->assertSame('u', $encoder->encode($update)['op']);
+        $this->assertSame(['id' => 1], $encoder->encode($update)['before']);
+        $this->assertSame(['id' => 2], $encoder->encode($update)['after']);
+        $this->assertSame('d', $encoder->encode($delete)['op']);
+        $this->assertNull($encoder->encode($delete)['after']);
+    }
+
+    public function testEncodeJson(): void
+    {
+        $event = new DeleteEvent(EventInterface::DELETE, 1760000000, 'checkpoint', 'mydb', 'users', (object)['id' => 1]);
+
+        $envelope = json_decode((new DebeziumEncoder())->encodeJson($event), true);
+
+        $this->assertSame('d', $envelope['op']);
+        $this->assertSame(['id' => 1], $envelope['before']);
+    }
+}
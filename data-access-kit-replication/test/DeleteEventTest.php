@@ -41,4 +41,56 @@ class DeleteEventTest extends TestCase
         $this->assertEquals('users', $event->table);
         $this->assertEquals($beforeData, $event->before);
     }
-}
\ No newline at end of file
+
+    public function testCanConstructClassWithPosition(): void
+    {
+        $event = new DeleteEvent(
+            EventInterface::DELETE,
+            1760000000,
+            'checkpoint789',
+            'mydb',
+            'users',
+            (object)['id' => 1],
+            1,
+            'mysql-bin.000003',
+            1234,
+            '3E11FA47-71CA-11E1-9E33-C80AA9429562:23',
+            2
+        );
+
+        $this->assertEquals(1760000000, $event->timestamp);
+        $this->assertEquals(1, $event->serverId);
+        $this->assertEquals('mysql-bin.000003', $event->binlogFile);
+        $this->assertEquals(1234, $event->binlogPosition);
+        $this->assertEquals('3E11FA47-71CA-11E1-9E33-C80AA9429562:23', $event->gtid);
+        $this->assertEquals(2, $event->ordinal);
+    }
+
+    public function testArrayRoundTrip(): void
+    {
+        $event = new DeleteEvent(EventInterface::DELETE, 1760000000, 'checkpoint789', 'mydb', 'users', (object)['id' => 1]);
+
+        $array = $event->toArray();
+        $this->assertNull($array['after']);
+
+        $rebuilt = DeleteEvent::fromArray(json_decode(json_encode($event), true));
+        $this->assertInstanceOf(DeleteEvent::class, $rebuilt);
+        $this->assertEquals($event, $rebuilt);
+    }
+
+    public function testMetadataPropertiesAreReadOnly(): void
+    {
+        $event = new DeleteEvent(EventInterface::DELETE, 1760000000, 'checkpoint123', 'mydb', 'users', (object)['id' => 1], 1, 'mysql-bin.000003', 1234);
+
+        $writable = [];
+        foreach (['serverId', 'binlogFile', 'binlogPosition', 'gtid', 'ordinal', 'query', 'columnTypes'] as $property) {
+            try {
+                $event->$property = null;
+                $writable[] = $property;
+            } catch (\Exception $e) {
+            }
+        }
+        $this->assertSame([], $writable);
+        $this->assertSame(1234, $event->binlogPosition);
+    }
+}
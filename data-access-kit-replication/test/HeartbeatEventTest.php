@@ -0,0 +1,33 @@
+<?php
+
+namespace DataAccessKit\Replication\Test;
+
+use PHPUnit\Framework\Attributes\Group;
+use PHPUnit\Framework\TestCase;
+use DataAccessKit\Replication\{EventInterface, HeartbeatEvent};
+
+#[Group("unit")]
+class HeartbeatEventTest extends TestCase
+{
+    public function testClassExists(): void
+    {
+        $this->assertTrue(class_exists(HeartbeatEvent::class));
+    }
+
+    public function testClassIsNotRowEvent(): void
+    {
+        $this->assertFalse(is_subclass_of(HeartbeatEvent::class, EventInterface::class));
+    }
+
+    public function testCanConstructClassWithProperties(): void
+    {
+        $timestamp = time();
+
+        $event = new HeartbeatEvent($timestamp, 1, 'mysql-bin.000003', 1234);
+
+        $this->assertEquals($timestamp, $event->timestamp);
+        $this->assertEquals(1, $event->serverId);
+        $this->assertEquals('mysql-bin.000003', $event->binlogFile);
+        $this->assertEquals(1234, $event->binlogPosition);
+    }
+}
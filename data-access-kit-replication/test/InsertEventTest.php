@@ -42,4 +42,134 @@ class InsertEventTest extends TestCase
         $this->assertEquals($afterData, $event->after);
     }
 
-}
\ No newline at end of file
+    public function testCanConstructClassWithPosition(): void
+    {
+        $event = new InsertEvent(
+            EventInterface::INSERT,
+            1760000000,
+            'checkpoint123',
+            'mydb',
+            'users',
+            (object)['id' => 1],
+            1,
+            'mysql-bin.000003',
+            1234,
+            '3E11FA47-71CA-11E1-9E33-C80AA9429562:23',
+            2,
+            'INSERT INTO users (id) VALUES (1) /* request_id=42 */'
+        );
+
+        $this->assertEquals(1760000000, $event->timestamp);
+        $this->assertEquals(1, $event->serverId);
+        $this->assertEquals('mysql-bin.000003', $event->binlogFile);
+        $this->assertEquals(1234, $event->binlogPosition);
+        $this->assertEquals('3E11FA47-71CA-11E1-9E33-C80AA9429562:23', $event->gtid);
+        $this->assertEquals(2, $event->ordinal);
+        $this->assertEquals('INSERT INTO users (id) VALUES (1) /* request_id=42 */', $event->query);
+    }
+
+    public function testArrayRoundTrip(): void
+    {
+        $afterData = (object)[
+            'id' => 1,
+            'price' => '123.45',
+            'created_at' => new \DateTimeImmutable('2024-01-15 14:30:45.123456', new \DateTimeZone('Europe/Prague')),
+            'payload' => "\xff\x00\xfe",
+            'tags' => ['read', 'write'],
+            'meta' => (object)['key' => 'value'],
+            'deleted_at' => null,
+        ];
+
+        $event = new InsertEvent(EventInterface::INSERT, 1760000000, 'checkpoint123', 'mydb', 'users', $afterData);
+
+        $array = $event->toArray();
+        $this->assertSame('INSERT', $array['type']);
+        $this->assertNull($array['before']);
+        $this->assertSame('123.45', $array['after']['price']);
+        $this->assertSame(['$type' => 'binary', 'value' => base64_encode("\xff\x00\xfe")], $array['after']['payload']);
+
+        $rebuilt = InsertEvent::fromArray(json_decode(json_encode($event), true));
+        $this->assertInstanceOf(InsertEvent::class, $rebuilt);
+        $this->assertEquals($event, $rebuilt);
+        $this->assertSame("\xff\x00\xfe", $rebuilt->after->payload);
+        $this->assertSame('2024-01-15 14:30:45.123456 Europe/Prague', $rebuilt->after->created_at->format('Y-m-d H:i:s.u e'));
+    }
+
+    public function testArrayRoundTripOfEmptyRow(): void
+    {
+        $event = new InsertEvent(EventInterface::INSERT, 1760000000, 'checkpoint123', 'mydb', 'users', new \stdClass());
+
+        $array = $event->toArray();
+        $this->assertSame([], $array['after']);
+        $this->assertNull($array['before']);
+
+        $rebuilt = InsertEvent::fromArray(json_decode(json_encode($event), true));
+        $this->assertEquals(new \stdClass(), $rebuilt->after);
+        $this->assertEquals($event, $rebuilt);
+    }
+
+    public function testArrayRoundTripOfEmptyJsonObject(): void
+    {
+        $afterData = (object)[
+            'id' => 1,
+            'settings' => (object)[],
+            'profile' => (object)['settings' => (object)[]],
+        ];
+        $event = new InsertEvent(EventInterface::INSERT, 1760000000, 'checkpoint123', 'mydb', 'users', $afterData);
+
+        $rebuilt = InsertEvent::fromArray($event->toArray());
+        $this->assertEquals($afterData, $rebuilt->after);
+
+        $rebuilt = InsertEvent::fromArray(json_decode(json_encode($event), true));
+        $this->assertEquals(new \stdClass(), $rebuilt->after->settings);
+        $this->assertEquals(new \stdClass(), $rebuilt->after->profile->settings);
+        $this->assertEquals($event, $rebuilt);
+    }
+
+    public function testArrayVersion(): void
+    {
+        $event = new InsertEvent(EventInterface::INSERT, 1760000000, 'checkpoint123', 'mydb', 'users', (object)['id' => 1]);
+        $array = $event->toArray();
+        $this->assertSame(2, $array['version']);
+
+        // Written before query and columnTypes were added
+        $array1 = $array;
+        unset($array1['version'], $array1['query'], $array1['columnTypes']);
+        $rebuilt = InsertEvent::fromArray($array1);
+        $this->assertNull($rebuilt->query);
+        $this->assertNull($rebuilt->columnTypes);
+        $this->assertEquals($event->after, $rebuilt->after);
+
+        $array['version'] = 3;
+        $this->expectException(\Exception::class);
+        $this->expectExceptionMessage('Unsupported event data version 3');
+        InsertEvent::fromArray($array);
+    }
+
+    public function testFromArrayRejectsOtherType(): void
+    {
+        $event = new InsertEvent(EventInterface::INSERT, 1760000000, 'checkpoint123', 'mydb', 'users', (object)['id' => 1]);
+        $array = $event->toArray();
+        $array['type'] = EventInterface::DELETE;
+
+        $this->expectException(\Exception::class);
+        $this->expectExceptionMessage('Expected event type INSERT, got DELETE');
+        InsertEvent::fromArray($array);
+    }
+
+    public function testMetadataPropertiesAreReadOnly(): void
+    {
+        $event = new InsertEvent(EventInterface::INSERT, 1760000000, 'checkpoint123', 'mydb', 'users', (object)['id' => 1], 1, 'mysql-bin.000003', 1234);
+
+        $writable = [];
+        foreach (['serverId', 'binlogFile', 'binlogPosition', 'gtid', 'ordinal', 'query', 'columnTypes'] as $property) {
+            try {
+                $event->$property = null;
+                $writable[] = $property;
+            } catch (\Exception $e) {
+            }
+        }
+        $this->assertSame([], $writable);
+        $this->assertSame(1234, $event->binlogPosition);
+    }
+}
@@ -157,4 +157,172 @@ class StreamFilterIntegrationTest extends AbstractIntegrationTestCase
             }
         }
     }
-}
\ No newline at end of file
+
+    public function testFilterErrorIsLogged(): void
+    {
+        $this->requireDatabase();
+
+        $filter = new class implements StreamFilterInterface {
+            public function accept(string $type, string $schema, string $table): bool {
+                if ($table === 'broken_table') {
+                    throw new \RuntimeException('Filter failure');
+                }
+                return true;
+            }
+        };
+
+        $logger = new class {
+            public array $records = [];
+
+            public function log($level, string|\Stringable $message, array $context = []): void {
+                $this->records[] = ['level' => $level, 'message' => (string) $message, 'context' => $context];
+            }
+        };
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_filter_db`");
+            $this->pdo->exec("USE `test_filter_db`");
+            $this->pdo->exec("CREATE TABLE IF NOT EXISTS `broken_table` (id INT AUTO_INCREMENT PRIMARY KEY)");
+            $this->pdo->exec("CREATE TABLE IF NOT EXISTS `allowed_table` (id INT AUTO_INCREMENT PRIMARY KEY)");
+
+            $stream = new Stream($this->createReplicationConnectionUrl(['database' => 'test_filter_db']));
+            $stream->setFilter($filter);
+            $stream->setLogger($logger);
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `broken_table` VALUES ()");
+            $this->pdo->exec("INSERT INTO `allowed_table` VALUES ()");
+
+            // The event the filter failed on is skipped
+            $stream->rewind();
+            $this->assertTrue($stream->valid());
+            $this->assertEquals('allowed_table', $stream->current()->table);
+
+            $errors = array_values(array_filter($logger->records, fn ($record) => $record['level'] === 'error'));
+            $this->assertCount(1, $errors);
+            $this->assertEquals('INSERT', $errors[0]['context']['type']);
+            $this->assertEquals('test_filter_db', $errors[0]['context']['schema']);
+            $this->assertEquals('broken_table', $errors[0]['context']['table']);
+            // Checkpoint of the skipped event, so it can be replayed once the filter is fixed
+            $this->assertNotEmpty($errors[0]['context']['checkpoint']);
+            $this->assertNotEquals($stream->current()->checkpoint, $errors[0]['context']['checkpoint']);
+            $this->assertNotEmpty($errors[0]['context']['error']);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (\Exception $e) {
+                }
+            }
+        }
+    }
+
+    public function testFilterErrorWithThrowPolicy(): void
+    {
+        $this->requireDatabase();
+
+        $filter = new class implements StreamFilterInterface {
+            public function accept(string $type, string $schema, string $table): bool {
+                throw new \RuntimeException('Filter failure');
+            }
+        };
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_filter_db`");
+            $this->pdo->exec("USE `test_filter_db`");
+            $this->pdo->exec("CREATE TABLE IF NOT EXISTS `broken_table` (id INT AUTO_INCREMENT PRIMARY KEY)");
+
+            $stream = new Stream(
+                $this->createReplicationConnectionUrl(['database' => 'test_filter_db']),
+                ['on_error' => 'throw'],
+            );
+            $stream->setFilter($filter);
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `broken_table` VALUES ()");
+
+            $this->expectException(\Exception::class);
+            $this->expectExceptionMessage('Filter failed for INSERT event on test_filter_db.broken_table');
+            $stream->rewind();
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (\Exception $e) {
+                }
+            }
+        }
+    }
+
+    public function testFilterErrorWithDeadLetterPolicy(): void
+    {
+        $this->requireDatabase();
+
+        $filter = new class implements StreamFilterInterface {
+            public function accept(string $type, string $schema, string $table): bool {
+                if ($table === 'broken_table') {
+                    throw new \RuntimeException('Filter failure');
+                }
+                return true;
+            }
+        };
+
+        $deadLetters = [];
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_filter_db`");
+            $this->pdo->exec("USE `test_filter_db`");
+            $this->pdo->exec("CREATE TABLE IF NOT EXISTS `broken_table` (id INT PRIMARY KEY, name VARCHAR(100), data VARBINARY(4))");
+            $this->pdo->exec("CREATE TABLE IF NOT EXISTS `allowed_table` (id INT AUTO_INCREMENT PRIMARY KEY)");
+
+            $stream = new Stream(
+                $this->createReplicationConnectionUrl(['database' => 'test_filter_db']),
+                ['on_error' => function (array $deadLetter) use (&$deadLetters) {
+                    $deadLetters[] = $deadLetter;
+                }],
+            );
+            $stream->setFilter($filter);
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `broken_table` VALUES (1, 'First', 0x61), (2, 'Second', 0xFF00)");
+            $this->pdo->exec("INSERT INTO `allowed_table` VALUES ()");
+
+            $stream->rewind();
+            $this->assertTrue($stream->valid());
+            $this->assertEquals('allowed_table', $stream->current()->table);
+
+            $this->assertCount(2, $deadLetters);
+            $this->assertEquals('filter', $deadLetters[0]['reason']);
+            $this->assertEquals('INSERT', $deadLetters[0]['type']);
+            $this->assertEquals('test_filter_db', $deadLetters[0]['schema']);
+            $this->assertEquals('broken_table', $deadLetters[0]['table']);
+            $this->assertNotEmpty($deadLetters[0]['checkpoint']);
+            $this->assertEquals(1, $deadLetters[1]['ordinal']);
+            $this->assertNull($deadLetters[0]['before']);
+            $this->assertEquals(['id', 'name', 'data'], array_keys($deadLetters[0]['after']));
+            $this->assertSame(2, $deadLetters[1]['after']['id']);
+            $this->assertSame('Second', $deadLetters[1]['after']['name']);
+            // Binary values are tagged and base64 encoded, the same as in event JSON
+            $this->assertSame('a', $deadLetters[0]['after']['data']);
+            $this->assertSame(
+                ['$type' => 'binary', 'value' => base64_encode("\xFF\x00")],
+                $deadLetters[1]['after']['data'],
+            );
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (\Exception $e) {
+                }
+            }
+        }
+    }
+}
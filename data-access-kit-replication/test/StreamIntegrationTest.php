@@ -5,10 +5,13 @@ namespace DataAccessKit\Replication\Test;
 use PHPUnit\Framework\Attributes\Group;
 use PHPUnit\Framework\Attributes\DataProvider;
 use DataAccessKit\Replication\Stream;
+use DataAccessKit\Replication\CheckpointUnavailableException;
 use DataAccessKit\Replication\EventInterface;
 use DataAccessKit\Replication\InsertEvent;
 use DataAccessKit\Replication\UpdateEvent;
 use DataAccessKit\Replication\DeleteEvent;
+use DataAccessKit\Replication\HeartbeatEvent;
+use DataAccessKit\Replication\StreamCheckpointerInterface;
 use Exception;
 
 #[Group("database")]
@@ -70,6 +73,12 @@ class StreamIntegrationTest extends AbstractIntegrationTestCase
             $this->assertIsObject($insertEvent->after);
             $this->assertEquals('John Doe', $insertEvent->after->name);
             $this->assertEquals('john@example.com', $insertEvent->after->email);
+            $this->assertGreaterThan(0, $insertEvent->serverId);
+            $this->assertNotEmpty($insertEvent->binlogFile);
+            $this->assertGreaterThan(0, $insertEvent->binlogPosition);
+            $this->assertEquals(0, $insertEvent->ordinal);
+            $this->assertSame('long', $insertEvent->columnTypes['id']);
+            $this->assertSame('string', $insertEvent->columnTypes['name']);
 
             // Test 5: Update the row to generate UPDATE event
             $testPdo->exec("
@@ -254,6 +263,73 @@ class StreamIntegrationTest extends AbstractIntegrationTestCase
         }
     }
 
+    public function testMysqlWithoutGtidMode(): void
+    {
+        $this->requireDatabase();
+
+        $stmt = $this->pdo->query("SELECT VERSION()");
+        if (stripos($stmt->fetchColumn(), 'mariadb') !== false) {
+            $this->markTestSkipped('This test is for MySQL only');
+        }
+
+        $stmt = $this->pdo->query("SELECT @@GLOBAL.gtid_mode");
+        $originalGtidMode = $stmt->fetchColumn();
+
+        $stream = null;
+
+        try {
+            if ($originalGtidMode === 'ON') {
+                $this->pdo->exec("SET @@GLOBAL.gtid_mode = 'ON_PERMISSIVE'");
+                $this->pdo->exec("SET @@GLOBAL.gtid_mode = 'OFF_PERMISSIVE'");
+                $this->pdo->exec("SET @@GLOBAL.gtid_mode = 'OFF'");
+            }
+
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_no_gtid_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'require_gtid' => 'false',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_no_gtid_users` (name) VALUES ('User 1')");
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('User 1', $batch[0]->after->name);
+            $this->assertStringStartsWith('file:', $batch[0]->checkpoint);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+
+            if ($originalGtidMode === 'ON') {
+                try {
+                    $this->pdo->exec("SET @@GLOBAL.gtid_mode = 'OFF_PERMISSIVE'");
+                    $this->pdo->exec("SET @@GLOBAL.gtid_mode = 'ON_PERMISSIVE'");
+                    $this->pdo->exec("SET @@GLOBAL.gtid_mode = 'ON'");
+                } catch (Exception $e) {
+                }
+            }
+        }
+    }
+
     public static function dataTypeProvider(): array
     {
         return [
@@ -556,5 +632,1473 @@ class StreamIntegrationTest extends AbstractIntegrationTestCase
         }
     }
 
+    public function testFetchBatch(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_batch_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl(['database' => 'test_replication_db']));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_batch_users` (name) VALUES ('User 1'), ('User 2'), ('User 3')");
+            $this->pdo->exec("UPDATE `test_batch_users` SET name = CONCAT(name, ' - Updated')");
+
+            // 3 inserts + 3 updates, capped at 4 per batch
+            $batch = $stream->fetchBatch(4, 5.0);
+            $this->assertCount(4, $batch);
+            $this->assertContainsOnlyInstancesOf(EventInterface::class, $batch);
+            $this->assertEquals(EventInterface::INSERT, $batch[0]->type);
+            $this->assertEquals('User 1', $batch[0]->after->name);
+
+            $batch = $stream->fetchBatch(100, 1.0);
+            $this->assertCount(2, $batch);
+            $this->assertEquals(EventInterface::UPDATE, $batch[1]->type);
+            $this->assertEquals('User 3 - Updated', $batch[1]->after->name);
+
+            // Nothing else arrives, the call returns empty after the wait
+            $startedAt = microtime(true);
+            $this->assertSame([], $stream->fetchBatch(100, 0.5));
+            $this->assertGreaterThanOrEqual(0.4, microtime(true) - $startedAt);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testFetchBatchWithoutWaiting(): void
+    {
+        $this->requireDatabase();
+
+        $streams = [];
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_poll_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            // Read directly from the socket, and from the prefetch buffer
+            foreach ([[], ['prefetch' => '10']] as $options) {
+                $stream = new Stream($this->createReplicationConnectionUrl(['database' => 'test_replication_db'] + $options));
+                $streams[] = $stream;
+                $this->assertSame([], $stream->fetchBatch(10, 0.0));
+            }
+
+            $this->pdo->exec("INSERT INTO `test_poll_users` (name) VALUES ('User 1'), ('User 2')");
+            usleep(500000);
+
+            foreach ($streams as $stream) {
+                $startedAt = microtime(true);
+                $batch = $stream->fetchBatch(10, 0.0);
+                $this->assertLessThan(0.2, microtime(true) - $startedAt);
+                $this->assertCount(2, $batch);
+                $this->assertEquals('User 2', $batch[1]->after->name);
+                $this->assertSame([], $stream->fetchBatch(10, 0.0));
+            }
+
+        } finally {
+            foreach ($streams as $stream) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testIdleTimeout(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_idle_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'idle_timeout' => '0.5',
+            ]));
+            $stream->connect();
+
+            // No events - iteration ends after the idle timeout instead of blocking
+            $startedAt = microtime(true);
+            $stream->rewind();
+            $this->assertFalse($stream->valid());
+            $this->assertGreaterThanOrEqual(0.4, microtime(true) - $startedAt);
+
+            $this->pdo->exec("INSERT INTO `test_idle_users` (name) VALUES ('User 1')");
+
+            // Reading resumes where it stopped
+            $stream->next();
+            $this->assertTrue($stream->valid());
+            $this->assertEquals(0, $stream->key());
+            $this->assertEquals('User 1', $stream->current()->after->name);
+
+            $stream->next();
+            $this->assertFalse($stream->valid());
+
+            $this->pdo->exec("INSERT INTO `test_idle_users` (name) VALUES ('User 2')");
+
+            // foreach resumes as well instead of starting over
+            $names = [];
+            foreach ($stream as $key => $event) {
+                $names[$key] = $event->after->name;
+            }
+            $this->assertEquals([1 => 'User 2'], $names);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testPrefetch(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_prefetch_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            // Buffer smaller than the number of events to exercise backpressure
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'prefetch' => '2',
+                'idle_timeout' => '1',
+            ]));
+            $stream->connect();
+
+            for ($i = 1; $i <= 5; $i++) {
+                $this->pdo->exec("INSERT INTO `test_prefetch_users` (name) VALUES ('User {$i}')");
+            }
+
+            $names = [];
+            foreach ($stream as $event) {
+                $names[] = $event->after->name;
+            }
+
+            $this->assertEquals(['User 1', 'User 2', 'User 3', 'User 4', 'User 5'], $names);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testPrefetchReadsWhilePhpIsBusy(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_prefetch_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'prefetch' => '100',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_prefetch_users` (name) VALUES ('User 1')");
+            $this->assertCount(1, $stream->fetchBatch(1, 5.0));
+
+            // Written while PHP does not call the stream, only the prefetch thread can read it
+            $this->pdo->exec("INSERT INTO `test_prefetch_users` (name) VALUES ('User 2')");
+            usleep(500000);
+            $this->assertGreaterThan(0, $stream->getStats()['prefetchBuffered']);
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertSame('User 2', $batch[0]->after->name);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testStop(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_stop_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $checkpointer = new class implements StreamCheckpointerInterface {
+                public array $saved = [];
+
+                public function loadLastCheckpoint(): ?string
+                {
+                    return null;
+                }
+
+                public function saveCheckpoint(string $checkpoint): void
+                {
+                    $this->saved[] = $checkpoint;
+                }
+            };
+
+            $stream = new Stream($this->createReplicationConnectionUrl(['database' => 'test_replication_db']));
+            $stream->setCheckpointer($checkpointer);
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_stop_users` (name) VALUES ('User 1'), ('User 2')");
+            $this->pdo->exec("INSERT INTO `test_stop_users` (name) VALUES ('User 3')");
+
+            // Rows of the event in progress are delivered, later events are not
+            $names = [];
+            foreach ($stream as $event) {
+                $names[] = $event->after->name;
+                $stream->stop();
+            }
 
-}
\ No newline at end of file
+            $this->assertEquals(['User 1', 'User 2'], $names);
+            $this->assertFalse($stream->valid());
+            $this->assertEquals($event->checkpoint, end($checkpointer->saved));
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testHeartbeatEvents(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'heartbeat_interval' => '1',
+                'read_timeout' => '5',
+                'heartbeat_events' => 'true',
+            ]));
+            $stream->connect();
+
+            // No changes, the server sends a heartbeat within the interval
+            $batch = $stream->fetchBatch(1, 3.0);
+
+            $this->assertCount(1, $batch);
+            $this->assertInstanceOf(HeartbeatEvent::class, $batch[0]);
+            $this->assertGreaterThanOrEqual(time() - 5, $batch[0]->timestamp);
+            $this->assertNotEmpty($batch[0]->binlogFile);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+        }
+    }
+
+    public function testCredentialsProvider(): void
+    {
+        $this->requireDatabase();
+
+        $calls = 0;
+        $config = $this->replicationConfig;
+        $stream = new Stream(
+            sprintf('mysql://%s:%d?server_id=100', $config['host'], $config['port']),
+            [
+                'credentials' => function () use (&$calls, $config) {
+                    $calls++;
+                    return ['user' => $config['user'], 'password' => $config['password'] ?? ''];
+                },
+            ],
+        );
+
+        try {
+            $stream->connect();
+            $this->assertEquals(1, $calls);
+
+            // Credentials are fetched again on reconnect
+            $stream->disconnect();
+            $stream->connect();
+            $this->assertEquals(2, $calls);
+        } finally {
+            $stream->disconnect();
+        }
+    }
+
+    public function testNoLagWhileIdleBeforeHeartbeat(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_stats_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_stats_users` (name) VALUES ('User 1')");
+            $this->assertCount(1, $stream->fetchBatch(1, 5.0));
+
+            // Nothing arrives, long before the next heartbeat, and the stream is still caught up
+            sleep(2);
+            $this->assertSame([], $stream->fetchBatch(1, 1.0));
+
+            $stats = $stream->getStats();
+            $this->assertNull($stats['lastHeartbeat']);
+            $this->assertSame(0, $stats['secondsBehindSource']);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testSignalHandlerExceptionEndsRead(): void
+    {
+        $this->requireDatabase();
+        if (!function_exists('pcntl_signal')) {
+            $this->markTestSkipped('pcntl extension not available');
+        }
+
+        $stream = null;
+        $asyncSignals = pcntl_async_signals(false);
+
+        try {
+            $stream = new Stream($this->createReplicationConnectionUrl());
+            $stream->connect();
+
+            pcntl_signal(SIGALRM, function () {
+                throw new \RuntimeException('Interrupted');
+            });
+            pcntl_alarm(1);
+
+            // Nothing to read, only the signal handler ends the wait early
+            $started = microtime(true);
+            $thrown = null;
+            try {
+                $stream->fetchBatch(1, 10.0);
+            } catch (Exception $e) {
+                $thrown = $e;
+            }
+            $this->assertNotNull($thrown);
+            $this->assertLessThan(5.0, microtime(true) - $started);
+
+        } finally {
+            pcntl_alarm(0);
+            pcntl_signal(SIGALRM, SIG_DFL);
+            pcntl_async_signals($asyncSignals);
+
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+        }
+    }
+
+    public function testGetStats(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_stats_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'heartbeat_interval' => '1',
+                'read_timeout' => '5',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_stats_users` (name) VALUES ('User 1'), ('User 2')");
+
+            $batch = $stream->fetchBatch(2, 5.0);
+            $this->assertCount(2, $batch);
+
+            $stats = $stream->getStats();
+            $this->assertEquals(2, $stats['eventsEmitted']);
+            $this->assertGreaterThan(2, $stats['eventsRead']);
+            $this->assertGreaterThan(0, $stats['bytesRead']);
+            $this->assertEquals(0, $stats['reconnects']);
+            $this->assertEquals($batch[1]->checkpoint, $stats['checkpoint']);
+            $this->assertGreaterThanOrEqual(0, $stats['secondsBehindSource']);
+
+            $metrics = $stream->getMetrics();
+            $this->assertStringContainsString('data_access_kit_replication_events_emitted_total{', $metrics);
+            $this->assertStringContainsString(',stream="default",type="INSERT"} 2', $metrics);
+            $this->assertStringContainsString(',stream="default",schema="test_replication_db",table="test_stats_users"} 2', $metrics);
+
+            // Waiting for more events lets a heartbeat arrive, the stream is caught up
+            $this->assertSame([], $stream->fetchBatch(1, 2.5));
+
+            $stats = $stream->getStats();
+            $this->assertNotNull($stats['lastHeartbeat']);
+            $this->assertSame(0, $stats['secondsBehindSource']);
+
+            // Reopening the binlog connection on purpose is not a reconnect
+            $stream->rewind();
+            $this->assertSame(0, $stream->getStats()['reconnects']);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testStartFromTimestamp(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_start_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $this->pdo->exec("INSERT INTO `test_start_users` (name) VALUES ('Before')");
+            sleep(2);
+            $startAt = time();
+            $this->pdo->exec("INSERT INTO `test_start_users` (name) VALUES ('After')");
+
+            // Both inserts happened before the stream was created
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'start' => "timestamp:{$startAt}",
+            ]));
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('After', $batch[0]->after->name);
+            $this->assertGreaterThanOrEqual($startAt, $batch[0]->timestamp);
+
+            // An explicit checkpoint replays from that point
+            $checkpoint = $batch[0]->checkpoint;
+            $this->pdo->exec("INSERT INTO `test_start_users` (name) VALUES ('Later')");
+            $stream->disconnect();
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'start' => $checkpoint,
+            ]));
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('Later', $batch[0]->after->name);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testBoundedReplay(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_until_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            sleep(1);
+            $startAt = time();
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('First'), ('Second')");
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('Third')");
+
+            // Replays what was written before iteration started, then ends
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'start' => "timestamp:{$startAt}",
+                'until' => 'current',
+            ]));
+
+            $names = [];
+            $startedAt = microtime(true);
+            foreach ($stream as $event) {
+                $names[] = $event->after->name;
+            }
+            $this->assertEquals(['First', 'Second', 'Third'], $names);
+            // Ends at the last event written, without waiting for a heartbeat
+            $this->assertLessThan(20, microtime(true) - $startedAt);
+            $this->assertFalse($stream->valid());
+            $stream->disconnect();
+
+            // Ends in the middle of the multi-row insert
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'start' => "timestamp:{$startAt}",
+                'max_events' => '1',
+            ]));
+
+            $batch = $stream->fetchBatch(10, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('First', $batch[0]->after->name);
+            $this->assertEquals([], $stream->fetchBatch(10, 0.1));
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testBoundedReplayUntilFilePosition(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_until_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            sleep(1);
+            $startAt = time();
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('First')");
+
+            // SHOW BINARY LOG STATUS replaces SHOW MASTER STATUS in MySQL 8.2+
+            try {
+                $status = $this->pdo->query("SHOW BINARY LOG STATUS")->fetch(\PDO::FETCH_ASSOC);
+            } catch (\PDOException $e) {
+                $status = $this->pdo->query("SHOW MASTER STATUS")->fetch(\PDO::FETCH_ASSOC);
+            }
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('Second')");
+
+            // The first insert's transaction ends at the position
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'start' => "timestamp:{$startAt}",
+                'until' => "file:{$status['File']}:{$status['Position']}",
+            ]));
+
+            $names = [];
+            foreach ($stream as $event) {
+                $names[] = $event->after->name;
+            }
+            $this->assertEquals(['First'], $names);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testBoundedReplayUntilGtid(): void
+    {
+        $this->requireDatabase();
+
+        $version = $this->pdo->query("SELECT VERSION()")->fetchColumn();
+        if (stripos($version, 'mariadb') !== false) {
+            $this->markTestSkipped('GTID set bounds are for MySQL only');
+        }
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_until_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            sleep(1);
+            $startAt = time();
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('First')");
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('Second')");
+            $gtidExecuted = $this->pdo->query("SELECT @@GLOBAL.gtid_executed")->fetchColumn();
+
+            // Ends once the transactions written so far have been read, without waiting for more
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'start' => "timestamp:{$startAt}",
+                'until' => 'gtid:' . str_replace("\n", '', $gtidExecuted),
+            ]));
+
+            $names = [];
+            $startedAt = microtime(true);
+            foreach ($stream as $event) {
+                $names[] = $event->after->name;
+            }
+            $this->assertEquals(['First', 'Second'], $names);
+            // Well within the default heartbeat_interval of 30 seconds
+            $this->assertLessThan(20, microtime(true) - $startedAt);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testBoundedReplayUntilTimestamp(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_until_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            sleep(1);
+            $startAt = time();
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('First')");
+            $until = time();
+            sleep(2);
+            $this->pdo->exec("INSERT INTO `test_until_users` (name) VALUES ('Second')");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'start' => "timestamp:{$startAt}",
+                'until' => "timestamp:{$until}",
+            ]));
+
+            $names = [];
+            foreach ($stream as $event) {
+                $names[] = $event->after->name;
+            }
+            $this->assertEquals(['First'], $names);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testUnavailableCheckpoint(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        $checkpointer = new class implements StreamCheckpointerInterface {
+            public function loadLastCheckpoint(): ?string
+            {
+                return 'file:purged-bin.000001:4';
+            }
+
+            public function saveCheckpoint(string $checkpoint): void
+            {
+            }
+        };
+
+        try {
+            $stream = new Stream($this->createReplicationConnectionUrl());
+            $stream->setCheckpointer($checkpointer);
+
+            try {
+                $stream->rewind();
+                $this->fail('Expected CheckpointUnavailableException');
+            } catch (CheckpointUnavailableException $e) {
+                $this->assertStringContainsString("Checkpoint 'file:purged-bin.000001:4' is not available", $e->getMessage());
+                $this->assertStringContainsString("binlog file 'purged-bin.000001' is not on the server", $e->getMessage());
+            }
+            $stream->disconnect();
+
+            // Falling back to latest streams new changes
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'on_checkpoint_unavailable' => 'latest',
+            ]));
+            $stream->setCheckpointer($checkpointer);
+            $stream->rewind();
+
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_checkpoint_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+            $this->pdo->exec("INSERT INTO `test_checkpoint_users` (name) VALUES ('User 1')");
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('User 1', $batch[0]->after->name);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testGtidCheckpointFormat(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_gtid_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'checkpoint_format' => 'gtid',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_gtid_users` (name) VALUES ('User 1')");
+            $this->pdo->exec("INSERT INTO `test_gtid_users` (name) VALUES ('User 2')");
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('User 1', $batch[0]->after->name);
+            $this->assertStringStartsWith('gtid:', $batch[0]->checkpoint);
+            $this->assertNotNull($batch[0]->gtid);
+
+            // Resuming from the GTID checkpoint continues with the next transaction
+            $checkpoint = $batch[0]->checkpoint;
+            $stream->disconnect();
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'checkpoint_format' => 'gtid',
+                'start' => $checkpoint,
+            ]));
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('User 2', $batch[0]->after->name);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testFailoverSkipsUnreachableHost(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_failover_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $host = "{$this->replicationConfig['host']}:{$this->replicationConfig['port']}";
+            $url = str_replace(
+                "@{$host}",
+                "@127.0.0.1:1,{$host}",
+                $this->createReplicationConnectionUrl([
+                    'database' => 'test_replication_db',
+                    'connect_timeout' => '1',
+                ])
+            );
+
+            $stream = new Stream($url);
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_failover_users` (name) VALUES ('User 1')");
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertEquals('User 1', $batch[0]->after->name);
+            $this->assertStringContainsString(
+                "host=\"{$host}\"",
+                $stream->getMetrics()
+            );
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testFailoverRedeliversOpenTransaction(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_failover_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(255) NOT NULL
+                )
+            ");
+
+            // The same host twice, the stream reconnects to it after the connection is lost
+            $host = "{$this->replicationConfig['host']}:{$this->replicationConfig['port']}";
+            $stream = new Stream(str_replace(
+                "@{$host}",
+                "@{$host},{$host}",
+                $this->createReplicationConnectionUrl(['database' => 'test_replication_db'])
+            ));
+            $stream->connect();
+
+            // Large enough not to fit in the socket buffers, so the connection is lost mid-transaction
+            $this->pdo->exec("SET SESSION cte_max_recursion_depth = 100000");
+            $this->pdo->beginTransaction();
+            $this->pdo->exec("INSERT INTO `test_failover_users` (name) VALUES ('First')");
+            $this->pdo->exec("
+                INSERT INTO `test_failover_users` (name)
+                WITH RECURSIVE seq (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 100000)
+                SELECT REPEAT('x', 200) FROM seq
+            ");
+            $this->pdo->commit();
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $first = $batch[0];
+            $this->assertEquals('First', $first->after->name);
+
+            $dumpThreads = $this->pdo->query("
+                SELECT ID FROM information_schema.PROCESSLIST
+                WHERE COMMAND LIKE 'Binlog Dump%' AND USER = " . $this->pdo->quote($this->replicationConfig['user'])
+            )->fetchAll(\PDO::FETCH_COLUMN);
+            foreach ($dumpThreads as $id) {
+                $this->pdo->exec("KILL {$id}");
+            }
+
+            // Delivery is at-least-once, the open transaction is read again from its start
+            $again = null;
+            $deadline = microtime(true) + 30;
+            while ($again === null && microtime(true) < $deadline) {
+                foreach ($stream->fetchBatch(1000, 1.0) as $event) {
+                    if ($event->after->name === 'First') {
+                        $again = $event;
+                        break;
+                    }
+                }
+            }
+
+            $this->assertNotNull($again);
+            $this->assertSame($first->gtid, $again->gtid);
+            $this->assertSame($first->checkpoint, $again->checkpoint);
+            $this->assertSame($first->ordinal, $again->ordinal);
+            $this->assertSame(1, $stream->getStats()['reconnects']);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testEventServerIdIsOriginServer(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_origin_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_origin_users` (name) VALUES ('User 1')");
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+
+            // The stream's own server_id (100) identifies the client, not the origin
+            $originServerId = (int) $this->pdo->query("SELECT @@GLOBAL.server_id")->fetchColumn();
+            $this->assertSame($originServerId, $batch[0]->serverId);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testLoopPreventionSkipsMarkedTransactions(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_loop_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `replicator_writes` (
+                    id INT AUTO_INCREMENT PRIMARY KEY
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'ignore_marker_table' => 'test_replication_db.replicator_writes',
+            ]));
+            $stream->connect();
+
+            // Written back by the replicator, marked by the marker table write
+            $this->pdo->beginTransaction();
+            $this->pdo->exec("INSERT INTO `replicator_writes` VALUES ()");
+            $this->pdo->exec("INSERT INTO `test_loop_users` (name) VALUES ('Replicated')");
+            $this->pdo->commit();
+
+            $this->pdo->exec("INSERT INTO `test_loop_users` (name) VALUES ('Application')");
+
+            // Rows written before the marker are not recognized, the marker must come first
+            $this->pdo->beginTransaction();
+            $this->pdo->exec("INSERT INTO `test_loop_users` (name) VALUES ('Before marker')");
+            $this->pdo->exec("INSERT INTO `replicator_writes` VALUES ()");
+            $this->pdo->exec("INSERT INTO `test_loop_users` (name) VALUES ('After marker')");
+            $this->pdo->commit();
+
+            $batch = $stream->fetchBatch(2, 5.0);
+            $this->assertCount(2, $batch);
+            $this->assertEquals('test_loop_users', $batch[0]->table);
+            $this->assertEquals('Application', $batch[0]->after->name);
+            $this->assertEquals('Before marker', $batch[1]->after->name);
+            $this->assertSame(4, $stream->getStats()['eventsFiltered']);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testRowEventsCarryOriginalQuery(): void
+    {
+        $this->requireDatabase();
+
+        $version = $this->pdo->query("SELECT VERSION()")->fetchColumn();
+        if (stripos($version, 'mariadb') !== false) {
+            $this->markTestSkipped('This test is for MySQL only');
+        }
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_query_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("SET SESSION binlog_rows_query_log_events = ON");
+            $query = "INSERT INTO `test_query_users` (name) VALUES ('User 1'), ('User 2') /* request_id=42 */";
+            $this->pdo->exec($query);
+
+            $this->pdo->exec("SET SESSION binlog_rows_query_log_events = OFF");
+            $this->pdo->exec("INSERT INTO `test_query_users` (name) VALUES ('User 3')");
+
+            $batch = $stream->fetchBatch(3, 5.0);
+            $this->assertCount(3, $batch);
+            $this->assertSame($query, $batch[0]->query);
+            $this->assertSame($query, $batch[1]->query);
+            $this->assertNull($batch[2]->query);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testRewindWithoutCheckpointerContinuesAtFilePosition(): void
+    {
+        $this->requireDatabase();
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_restart_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'checkpoint_format' => 'file',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_restart_users` (name) VALUES ('User 1')");
+            $this->pdo->exec("INSERT INTO `test_restart_users` (name) VALUES ('User 2')");
+
+            $stream->rewind();
+            $this->assertTrue($stream->valid());
+            $this->assertSame('User 1', $stream->current()->after->name);
+
+            // Reconnects at the binlog position read so far, not where connect() started
+            $stream->rewind();
+            $this->assertTrue($stream->valid());
+            $this->assertSame('User 2', $stream->current()->after->name);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testBinlogWithoutChecksums(): void
+    {
+        $this->requireDatabase();
+
+        $originalChecksum = $this->pdo->query("SELECT @@GLOBAL.binlog_checksum")->fetchColumn();
+        $compression = $this->pdo->query("SHOW GLOBAL VARIABLES LIKE 'binlog_transaction_compression'")->fetch(\PDO::FETCH_ASSOC);
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("SET GLOBAL binlog_checksum = 'NONE'");
+
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_checksum_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_checksum_users` (name) VALUES ('User 1')");
+            $expected = ['User 1'];
+
+            // Compressed transactions arrive as one Transaction_payload event (MySQL 8.0.20+)
+            if ($compression !== false) {
+                $this->pdo->exec("SET SESSION binlog_transaction_compression = ON");
+                $this->pdo->exec("INSERT INTO `test_checksum_users` (name) VALUES ('User 2'), ('User 3')");
+                $this->pdo->exec("SET SESSION binlog_transaction_compression = OFF");
+                $this->pdo->exec("INSERT INTO `test_checksum_users` (name) VALUES ('User 4')");
+                $expected = ['User 1', 'User 2', 'User 3', 'User 4'];
+            }
+
+            $batch = $stream->fetchBatch(count($expected), 5.0);
+            $this->assertSame($expected, array_map(fn ($event) => $event->after->name, $batch));
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("SET GLOBAL binlog_checksum = " . $this->pdo->quote($originalChecksum));
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testTlsBinlogConnection(): void
+    {
+        $this->requireDatabase();
+
+        $haveSsl = $this->pdo->query("SHOW GLOBAL VARIABLES LIKE 'have_ssl'")->fetch(\PDO::FETCH_ASSOC);
+        if (($haveSsl['Value'] ?? 'YES') !== 'YES') {
+            $this->markTestSkipped('Server does not support TLS');
+        }
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_tls_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            // REQUIRED encrypts both the metadata and the binlog connection
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'ssl-mode' => 'REQUIRED',
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_tls_users` (name) VALUES ('User 1')");
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertSame('User 1', $batch[0]->after->name);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+
+    public function testUnixSocket(): void
+    {
+        $this->requireDatabase();
+
+        $socket = $this->pdo->query("SELECT @@GLOBAL.socket")->fetchColumn();
+        if (!$socket || !file_exists($socket)) {
+            $this->markTestSkipped('Server socket is not reachable from the tests');
+        }
+
+        $stream = null;
+
+        try {
+            $this->pdo->exec("CREATE DATABASE IF NOT EXISTS `test_replication_db`");
+            $this->pdo->exec("USE `test_replication_db`");
+            $this->pdo->exec("
+                CREATE TABLE IF NOT EXISTS `test_socket_users` (
+                    id INT AUTO_INCREMENT PRIMARY KEY,
+                    name VARCHAR(100) NOT NULL
+                )
+            ");
+
+            // Unreachable TCP address, both connections must go through the socket
+            $this->replicationConfig['host'] = '10.255.255.1';
+            $stream = new Stream($this->createReplicationConnectionUrl([
+                'database' => 'test_replication_db',
+                'connect_timeout' => '5',
+                'socket' => $socket,
+            ]));
+            $stream->connect();
+
+            $this->pdo->exec("INSERT INTO `test_socket_users` (name) VALUES ('User 1')");
+
+            $batch = $stream->fetchBatch(1, 5.0);
+            $this->assertCount(1, $batch);
+            $this->assertSame('User 1', $batch[0]->after->name);
+
+        } finally {
+            if ($stream !== null) {
+                try {
+                    $stream->disconnect();
+                } catch (Exception $e) {
+                }
+            }
+
+            try {
+                $this->pdo->exec("DROP DATABASE IF EXISTS `test_replication_db`");
+            } catch (Exception $e) {
+            }
+        }
+    }
+}